@@ -0,0 +1,73 @@
+extern crate lwobj;
+
+use std::fmt::Write;
+use std::thread;
+use std::time::{Duration,Instant};
+use std::mem;
+use lwobj::{ObjData,ObjVisitor,FaceVertex,LoadOptions,visit,visit_bytes};
+
+struct Nothing;
+
+impl ObjVisitor for Nothing {}
+
+// Grid of `n`x`n` quads with texture coordinates and normals.
+fn grid(n : usize) -> String {
+    let mut s = String::new();
+    for i in 0..n+1 {
+        for j in 0..n+1 {
+            let (x,y) = (i as f32 / n as f32,j as f32 / n as f32);
+            writeln!(s,"v {:.6} {:.6} {:.6}",x,y,(x*y).sin()).unwrap();
+            writeln!(s,"vt {:.6} {:.6}",x,y).unwrap();
+            writeln!(s,"vn 0.000000 0.000000 1.000000").unwrap();
+        }
+    }
+    for i in 0..n {
+        for j in 0..n {
+            let a = i*(n+1) + j + 1;
+            let (b,c,d) = (a+1,a+n+2,a+n+1);
+            writeln!(s,"f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c} {d}/{d}/{d}").unwrap();
+        }
+    }
+    s
+}
+
+// Fastest of a few runs of `f`.
+fn time<F : FnMut()>(mut f : F) -> Duration {
+    (0..5).map(|_| {
+        let start = Instant::now();
+        f();
+        start.elapsed()
+    }).min().unwrap()
+}
+
+fn main() {
+    let obj = grid(500);
+    let load = time(|| {
+        ObjData::load(&mut obj.as_bytes()).ok().unwrap();
+    });
+    let parse_bytes = time(|| {
+        ObjData::parse_bytes(obj.as_bytes()).ok().unwrap();
+    });
+    let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let parse_bytes_parallel = time(|| {
+        ObjData::parse_bytes_parallel(obj.as_bytes(),threads).ok().unwrap();
+    });
+    let visit_read = time(|| {
+        visit(&mut obj.as_bytes(),&LoadOptions::default(),&mut Nothing).ok().unwrap();
+    });
+    let visit_slice = time(|| {
+        visit_bytes(obj.as_bytes(),&LoadOptions::default(),&mut Nothing).ok().unwrap();
+    });
+    // Memory used by faces, compared to a `Vec` allocated for each face
+    let faces = ObjData::parse_bytes(obj.as_bytes()).ok().unwrap().faces;
+    let vertices = mem::size_of_val(faces.vertices());
+    let flat = vertices + mem::size_of_val(faces.offsets());
+    let nested = vertices + faces.len() * mem::size_of::<Vec<FaceVertex>>();
+    println!("{} MB",obj.len() / 1_000_000);
+    println!("faces       : {} MB ({} MB with a Vec per face)",flat / 1_000_000,nested / 1_000_000);
+    println!("load        : {:?}",load);
+    println!("parse_bytes : {:?}",parse_bytes);
+    println!("parse_bytes_parallel ({} threads) : {:?}",threads,parse_bytes_parallel);
+    println!("visit       : {:?}",visit_read);
+    println!("visit_bytes : {:?}",visit_slice);
+}
@@ -0,0 +1,382 @@
+use std::io;
+use std::str;
+use obj::{Float,LoadingError,LoadOptions,SourceLine};
+use reader::parse_statement;
+use visitor::{ObjVisitor,VisitState};
+
+// Powers of 10 exactly represented by a f64.
+const POW10 : [f64; 23] = [1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11,
+    1e12, 1e13, 1e14, 1e15, 1e16, 1e17, 1e18, 1e19, 1e20, 1e21, 1e22];
+
+/// Parse bytes in memory with `options`, giving each statement to `visitor` like `visit`.
+///
+/// Vertices and faces are read directly from `bytes` without allocating, every other statement
+/// being read like `visit` does, so both give the same statements and errors.
+/// Lines can end with `\n` or `\r\n`.
+///
+/// # Examples
+///
+/// ```
+/// use lwobj::{Float,ObjVisitor,LoadOptions,visit_bytes};
+///
+/// struct VertexCounter {
+///     vertices : usize,
+/// }
+///
+/// impl ObjVisitor for VertexCounter {
+///     fn vertex(&mut self, _v : (Float,Float,Float,Float)) {
+///         self.vertices += 1;
+///     }
+/// }
+///
+/// let mut counter = VertexCounter { vertices : 0 };
+/// assert!(visit_bytes(b"v 0 0 0\r\nv 1 0 0\r\n",&LoadOptions::default(),&mut counter).is_ok());
+/// assert_eq!(2,counter.vertices);
+/// ```
+pub fn visit_bytes<V : ObjVisitor>(bytes : &[u8], options : &LoadOptions, visitor : &mut V) -> Result<(),LoadingError> {
+    let text = from_utf8(bytes)?;
+    let mut state = VisitState::new();
+    let mut refs = Vec::new();
+    for (i,text) in text.split('\n').enumerate() {
+        let line = SourceLine::new(i+1,text);
+        match parse_fast(line.text,&mut refs) {
+            Some(FastStatement::Vertex(v)) => state.vertex(visitor,v),
+            Some(FastStatement::Normal(vn)) => state.normal(visitor,vn),
+            Some(FastStatement::TexCoord(vt)) => state.texcoord(visitor,vt),
+            Some(FastStatement::Face) => {
+                state.face(visitor,&refs,&line,|k| fast_argument(line.text,k))?;
+                refs.clear();
+            },
+            None => if let Some(statement) = parse_statement(&line,options.lenient) {
+                state.apply(visitor,statement?,&line)?;
+            },
+        }
+    }
+    state.finish()
+}
+
+// Check that `bytes` is UTF-8 text, failing like reading it from a `BufRead`.
+pub fn from_utf8(bytes : &[u8]) -> Result<&str,LoadingError> {
+    str::from_utf8(bytes).map_err(|err| LoadingError::Io(io::Error::new(io::ErrorKind::InvalidData,err)))
+}
+
+// Statement read by `parse_fast`.
+pub enum FastStatement {
+    Vertex((Float,Float,Float,Float)),
+    Normal((Float,Float,Float)),
+    TexCoord((Float,Float,Float)),
+    // Face whose `v/vt/vn` references were pushed to the buffer given to `parse_fast`.
+    Face,
+}
+
+// Arguments of a line separated by ASCII whitespaces.
+struct Args<'a> {
+    text : &'a str,
+    pos : usize,
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let bytes = self.text.as_bytes();
+        while self.pos < bytes.len() && bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        if self.pos == bytes.len() {
+            return None;
+        }
+        let start = self.pos;
+        while self.pos < bytes.len() && !bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+        Some(&self.text[start..self.pos])
+    }
+}
+
+// Argument `i` following the keyword of a line read by `parse_fast`, to locate errors.
+pub fn fast_argument(text : &str, i : usize) -> &str {
+    Args { text, pos : 0 }.nth(i+1).unwrap_or("")
+}
+
+// Read the `v`, `vn`, `vt` or `f` statement of `text`, pushing the references of a face to `refs`.
+// `None` for any other line, or if the arguments aren't usual ones so that the statement
+// must be parsed by `parse_statement` like any other.
+#[allow(clippy::type_complexity)]
+pub fn parse_fast(text : &str, refs : &mut Vec<(isize,Option<isize>,Option<isize>)>) -> Option<FastStatement> {
+    let mut args = Args { text, pos : 0 };
+    match args.next()? {
+        "v" => {
+            let mut v = [0.,0.,0.,1.];
+            match parse_floats(args,&mut v)? {
+                3 | 4 => Some(FastStatement::Vertex((v[0],v[1],v[2],v[3]))),
+                _ => None,
+            }
+        },
+        "vn" => {
+            let mut vn = [0.;3];
+            match parse_floats(args,&mut vn)? {
+                3 => Some(FastStatement::Normal((vn[0],vn[1],vn[2]))),
+                _ => None,
+            }
+        },
+        "vt" => {
+            let mut vt = [0.;3];
+            match parse_floats(args,&mut vt)? {
+                1..=3 => Some(FastStatement::TexCoord((vt[0],vt[1],vt[2]))),
+                _ => None,
+            }
+        },
+        "f" => {
+            let start = refs.len();
+            for arg in args {
+                match parse_vertex_ref(arg.as_bytes()) {
+                    Some(vertex) => refs.push(vertex),
+                    None => {
+                        refs.truncate(start);
+                        return None;
+                    },
+                }
+            }
+            if refs.len() - start < 3 {
+                refs.truncate(start);
+                return None;
+            }
+            Some(FastStatement::Face)
+        },
+        _ => None,
+    }
+}
+
+// Parse the floats `args` into `values`, giving their number.
+// `None` if one of them isn't a float or if there are too many.
+fn parse_floats<'a, I : Iterator<Item=&'a str>>(args : I, values : &mut [Float]) -> Option<usize> {
+    let mut n = 0;
+    for arg in args {
+        *values.get_mut(n)? = parse_float(arg)?;
+        n += 1;
+    }
+    Some(n)
+}
+
+fn parse_float(s : &str) -> Option<Float> {
+    match parse_decimal(s.as_bytes()) {
+        Some(value) => Some(value),
+        None => s.parse().ok(),
+    }
+}
+
+// Parse a decimal float like `-12.5e-3`.
+// `None` if its value can't be exactly rounded with a few operations, like for `inf`
+// or more than 19 digits, which must then be parsed by `str::parse`.
+fn parse_decimal(s : &[u8]) -> Option<Float> {
+    let (negative,mut i) = match s.first() {
+        Some(b'-') => (true,1),
+        Some(b'+') => (false,1),
+        _ => (false,0),
+    };
+    let mut mantissa : u64 = 0;
+    let mut digits = 0;
+    let mut exponent : i32 = 0;
+    let mut fraction = false;
+    let mut empty = true;
+    while i < s.len() {
+        match s[i] {
+            c @ b'0'..=b'9' => {
+                empty = false;
+                if mantissa != 0 || c != b'0' {
+                    digits += 1;
+                    if digits > 19 {
+                        return None;
+                    }
+                    mantissa = mantissa*10 + (c - b'0') as u64;
+                }
+                if fraction {
+                    exponent -= 1;
+                }
+            },
+            b'.' if !fraction => fraction = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    if empty {
+        return None;
+    }
+    if i < s.len() {
+        if s[i] != b'e' && s[i] != b'E' {
+            return None;
+        }
+        let (negative,digits) = match s[i+1..].split_first() {
+            Some((b'-',rest)) => (true,rest),
+            Some((b'+',rest)) => (false,rest),
+            _ => (false,&s[i+1..]),
+        };
+        if digits.is_empty() || digits.len() > 4 {
+            return None;
+        }
+        let mut e = 0;
+        for &c in digits {
+            if !c.is_ascii_digit() {
+                return None;
+            }
+            e = e*10 + (c - b'0') as i32;
+        }
+        exponent += if negative { -e } else { e };
+    }
+    let value = if mantissa == 0 {
+        0.
+    } else {
+        if mantissa > 1 << 53 || exponent.abs() > 22 {
+            return None;
+        }
+        // Both operands are exact, so the result is the exact value rounded to a f64
+        let value = if exponent >= 0 {
+            mantissa as f64 * POW10[exponent as usize]
+        } else {
+            mantissa as f64 / POW10[-exponent as usize]
+        };
+        round(value)?
+    };
+    Some(if negative { -value } else { value })
+}
+
+// Round `value`, which is an exact value rounded to a f64, to a `Float`.
+#[cfg(not(feature = "f64"))]
+fn round(value : f64) -> Option<Float> {
+    // Rounding again gives the exact value rounded to a f32, unless the first rounding
+    // gave a value halfway between two f32 or the value isn't a normal f32
+    if !(f32::MIN_POSITIVE as f64..=f32::MAX as f64).contains(&value) || value.to_bits() & 0x1fff_ffff == 0x1000_0000 {
+        return None;
+    }
+    Some(value as f32)
+}
+
+#[cfg(feature = "f64")]
+fn round(value : f64) -> Option<Float> {
+    Some(value)
+}
+
+// Parse an index like `-12`, `None` if it isn't one or if it has more than 9 digits.
+fn parse_index(s : &[u8]) -> Option<isize> {
+    let (negative,digits) = match s.split_first() {
+        Some((b'-',rest)) => (true,rest),
+        _ => (false,s),
+    };
+    if digits.is_empty() || digits.len() > 9 {
+        return None;
+    }
+    let mut value : isize = 0;
+    for &c in digits {
+        if !c.is_ascii_digit() {
+            return None;
+        }
+        value = value*10 + (c - b'0') as isize;
+    }
+    Some(if negative { -value } else { value })
+}
+
+fn parse_optional_index(s : Option<&[u8]>) -> Option<Option<isize>> {
+    match s {
+        Some(s) if !s.is_empty() => parse_index(s).map(Some),
+        _ => Some(None),
+    }
+}
+
+// Parse a `v/vt/vn` reference of a `f` statement.
+fn parse_vertex_ref(arg : &[u8]) -> Option<(isize,Option<isize>,Option<isize>)> {
+    let mut index = arg.split(|&c| c == b'/');
+    let v = parse_index(index.next()?)?;
+    let vt = parse_optional_index(index.next())?;
+    let vn = parse_optional_index(index.next())?;
+    if index.next().is_some() {
+        return None;
+    }
+    Some((v,vt,vn))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use obj::*;
+    use bytes::*;
+
+    #[test]
+    fn parse_numbers() {
+        for s in ["0", "-0", "1", "-1.5", "+2.25", ".5", "5.", "0.000001", "123.456789", "-0.57735026",
+                  "1e10", "1.5E-7", "3.4028235e38", "1e-40", "16777217", "1e39", "inf", "-NaN"] {
+            let expected : Float = s.parse().unwrap();
+            let value = parse_float(s).unwrap();
+            assert!(value.to_bits() == expected.to_bits() || (value.is_nan() && expected.is_nan()),"{}",s);
+        }
+        for s in ["", "-", ".", "1..2", "1e", "e5", "1-", "0x10", "1e+"] {
+            assert!(parse_float(s).is_none(),"{}",s);
+        }
+        // Pseudo-random floats written in various ways
+        let mut seed : u32 = 12345;
+        for _ in 0..20000 {
+            seed = seed.wrapping_mul(1664525).wrapping_add(1013904223);
+            let value = f32::from_bits(seed);
+            if !value.is_finite() {
+                continue;
+            }
+            for s in [format!("{}",value), format!("{:e}",value), format!("{:.6}",value), format!("{:.9}",value)] {
+                let expected : Float = s.parse().unwrap();
+                assert_eq!(expected.to_bits(),parse_float(&s).unwrap().to_bits(),"{}",s);
+            }
+        }
+
+        assert_eq!(Some(-12),parse_index(b"-12"));
+        assert_eq!(Some(0),parse_index(b"0"));
+        assert_eq!(None,parse_index(b"1.5"));
+        assert_eq!(None,parse_index(b"-"));
+        assert_eq!(Some((3,None,Some(-1))),parse_vertex_ref(b"3//-1"));
+        assert_eq!(None,parse_vertex_ref(b"1/2/3/4"));
+    }
+
+    #[test]
+    fn parse_bytes() {
+        let cube = fs::read("cube.obj").unwrap();
+        let expected = ObjData::from_path("cube.obj").ok().unwrap();
+        let data = ObjData::parse_bytes(&cube).ok().unwrap();
+        assert_eq!(expected.vertices,data.vertices);
+        assert_eq!(expected.normals,data.normals);
+        assert_eq!(expected.faces,data.faces);
+        assert_eq!(expected.objects,data.objects);
+        assert_eq!(expected.groups,data.groups);
+
+        let obj_str = "v 1.5 -2 3e2\r\nv 0 1 0 0.5\r\nv +1 0 0\r\nvt 0.5\r\nvn 0 0 1\r\n\r\n# comment\r\n\
+                       f 1/1/1 2/1/1 -1//1\r\nf 1 2 3 1\r\ns 1\r\nl 1 2\r\nv\u{a0}1 2 3\r\nf 1 2 3";
+        let expected = ObjData::load(&mut obj_str.as_bytes()).ok().unwrap();
+        let data = ObjData::parse_bytes(obj_str.as_bytes()).ok().unwrap();
+        assert_eq!(vec![(1.5,-2.,300.,1.),(0.,1.,0.,0.5),(1.,0.,0.,1.),(1.,2.,3.,1.)],data.vertices);
+        assert_eq!(expected.vertices,data.vertices);
+        assert_eq!(expected.texcoords,data.texcoords);
+        assert_eq!(expected.normals,data.normals);
+        assert_eq!(expected.faces,data.faces);
+        assert_eq!(expected.lines,data.lines);
+        assert_eq!(expected.smoothing_groups,data.smoothing_groups);
+    }
+
+    #[test]
+    fn parse_bytes_errors() {
+        let errors = ["v 0 0 0\r\nv 1 0\r\n",
+                      "v 0 0 0\nv 1 0 a\n",
+                      "v 0 0 0\nf 1 2\n",
+                      "v 0 0 0\nf 1 -2 1\n",
+                      "v 0 0 0\nf 1 1/x 1\n",
+                      "v 0 0 0\nf 1 0 1\n",
+                      "v 0 0 0\nf 1 1 2\n",
+                      "v 0 0 0\nf 1 1 1/1/1/1\n",
+                      "vn 0 0 1 1\n"];
+        for obj_str in errors.iter() {
+            let expected = ObjData::load(&mut obj_str.as_bytes()).err().unwrap();
+            let error = ObjData::parse_bytes(obj_str.as_bytes()).err().unwrap();
+            assert_eq!(expected.to_string(),error.to_string());
+        }
+        match ObjData::parse_bytes(b"v 0 0 \xff\n").err().unwrap() {
+            LoadingError::Io(_) => {},
+            _ => panic!(),
+        };
+    }
+}
@@ -0,0 +1,268 @@
+use std::slice;
+use std::ops::{Index,IndexMut};
+use std::iter::FromIterator;
+
+/// Index of a vertex in `ObjData::vertices`, starting at 0.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct VertexIndex(pub usize);
+
+/// Index of texture coordinates in `ObjData::texcoords`, starting at 0.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct TexCoordIndex(pub usize);
+
+/// Index of a normal in `ObjData::normals`, starting at 0.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+pub struct NormalIndex(pub usize);
+
+/// Vertex of a face given by `v/vt/vn` in a `f` statement.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub struct FaceVertex {
+    /// Index of its position.
+    pub position : VertexIndex,
+    /// Index of its texture coordinates if it has some.
+    pub texcoord : Option<TexCoordIndex>,
+    /// Index of its normal if it has one.
+    pub normal : Option<NormalIndex>,
+}
+
+/// Face given by `f`, as a list of vertices borrowed from `Faces`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Face<'a> {
+    pub vertices : &'a [FaceVertex],
+}
+
+/// List of faces, storing the vertices of every face in one buffer.
+///
+/// The vertices of the face `i` are `vertices[offsets[i]..offsets[i+1]]`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Faces {
+    vertices : Vec<FaceVertex>,
+    offsets : Vec<usize>,
+}
+
+impl FaceVertex {
+    /// Create a vertex from the indices of its position, texture coordinates and normal.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{FaceVertex,VertexIndex,NormalIndex};
+    ///
+    /// let vertex = FaceVertex::new(3,None,Some(1));
+    /// assert_eq!(VertexIndex(3),vertex.position);
+    /// assert_eq!(Some(NormalIndex(1)),vertex.normal);
+    /// ```
+    pub fn new(position : usize, texcoord : Option<usize>, normal : Option<usize>) -> FaceVertex {
+        FaceVertex {
+            position : VertexIndex(position),
+            texcoord : texcoord.map(TexCoordIndex),
+            normal : normal.map(NormalIndex),
+        }
+    }
+}
+
+impl From<(usize,Option<usize>,Option<usize>)> for FaceVertex {
+    fn from((v,vt,vn) : (usize,Option<usize>,Option<usize>)) -> FaceVertex {
+        FaceVertex::new(v,vt,vn)
+    }
+}
+
+impl<'a> Face<'a> {
+    pub fn new(vertices : &'a [FaceVertex]) -> Face<'a> {
+        Face {
+            vertices,
+        }
+    }
+
+    /// Number of vertices.
+    pub fn len(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Iterate over the vertices.
+    pub fn iter(&self) -> slice::Iter<'a, FaceVertex> {
+        self.vertices.iter()
+    }
+
+    /// Indices of the positions of the vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{Face,FaceVertex,VertexIndex,NormalIndex};
+    ///
+    /// let vertices = [FaceVertex::new(0,None,Some(0)),FaceVertex::new(1,None,Some(0)),FaceVertex::new(2,None,None)];
+    /// let face = Face::new(&vertices);
+    /// let positions : Vec<_> = face.positions().collect();
+    /// assert_eq!(vec![VertexIndex(0),VertexIndex(1),VertexIndex(2)],positions);
+    /// let normals : Vec<_> = face.normals().collect();
+    /// assert_eq!(vec![Some(NormalIndex(0)),Some(NormalIndex(0)),None],normals);
+    /// ```
+    pub fn positions(&self) -> impl Iterator<Item=VertexIndex> + 'a {
+        self.vertices.iter().map(|vertex| vertex.position)
+    }
+
+    /// Indices of the texture coordinates of the vertices.
+    pub fn texcoords(&self) -> impl Iterator<Item=Option<TexCoordIndex>> + 'a {
+        self.vertices.iter().map(|vertex| vertex.texcoord)
+    }
+
+    /// Indices of the normals of the vertices.
+    pub fn normals(&self) -> impl Iterator<Item=Option<NormalIndex>> + 'a {
+        self.vertices.iter().map(|vertex| vertex.normal)
+    }
+}
+
+impl<'a> IntoIterator for Face<'a> {
+    type Item = &'a FaceVertex;
+    type IntoIter = slice::Iter<'a, FaceVertex>;
+
+    fn into_iter(self) -> slice::Iter<'a, FaceVertex> {
+        self.vertices.iter()
+    }
+}
+
+impl Faces {
+    pub fn new() -> Faces {
+        Faces {
+            vertices : Vec::new(),
+            offsets : vec![0],
+        }
+    }
+
+    /// Number of faces.
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Face `i`, `None` if there is no such face.
+    pub fn get(&self, i : usize) -> Option<Face<'_>> {
+        if i < self.len() {
+            Some(Face::new(&self[i]))
+        } else {
+            None
+        }
+    }
+
+    /// Add a face made of `vertices`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{Faces,FaceVertex};
+    ///
+    /// let mut faces = Faces::new();
+    /// faces.push(vec![FaceVertex::new(0,None,None),FaceVertex::new(1,None,None),FaceVertex::new(2,None,None)]);
+    /// faces.push((1..5).map(|v| FaceVertex::new(v,None,None)));
+    /// assert_eq!(2,faces.len());
+    /// assert_eq!(4,faces.get(1).unwrap().len());
+    /// assert_eq!(7,faces.vertices().len());
+    /// ```
+    pub fn push<I : IntoIterator<Item=FaceVertex>>(&mut self, vertices : I) {
+        self.vertices.extend(vertices);
+        self.offsets.push(self.vertices.len());
+    }
+
+    /// Iterate over the faces.
+    pub fn iter(&self) -> FacesIter<'_> {
+        FacesIter {
+            faces : self,
+            i : 0,
+        }
+    }
+
+    /// Vertices of every face, one face after the other.
+    pub fn vertices(&self) -> &[FaceVertex] {
+        &self.vertices
+    }
+
+    /// Vertices of every face, which can be changed without changing the faces they belong to.
+    pub fn vertices_mut(&mut self) -> &mut [FaceVertex] {
+        &mut self.vertices
+    }
+
+    /// Start of every face in `vertices`, followed by the number of vertices.
+    pub fn offsets(&self) -> &[usize] {
+        &self.offsets
+    }
+}
+
+impl Default for Faces {
+    fn default() -> Faces {
+        Faces::new()
+    }
+}
+
+/// Vertices of the face `i`.
+impl Index<usize> for Faces {
+    type Output = [FaceVertex];
+
+    fn index(&self, i : usize) -> &[FaceVertex] {
+        &self.vertices[self.offsets[i]..self.offsets[i+1]]
+    }
+}
+
+/// Vertices of the face `i`, which can be changed but not added or removed.
+impl IndexMut<usize> for Faces {
+    fn index_mut(&mut self, i : usize) -> &mut [FaceVertex] {
+        &mut self.vertices[self.offsets[i]..self.offsets[i+1]]
+    }
+}
+
+/// Collect faces, each one being given by its vertices.
+impl<F : IntoIterator<Item=FaceVertex>> FromIterator<F> for Faces {
+    fn from_iter<I : IntoIterator<Item=F>>(faces : I) -> Faces {
+        let mut result = Faces::new();
+        for face in faces {
+            result.push(face);
+        }
+        result
+    }
+}
+
+/// Iterator over the faces of `Faces`.
+pub struct FacesIter<'a> {
+    faces : &'a Faces,
+    i : usize,
+}
+
+impl<'a> Iterator for FacesIter<'a> {
+    type Item = Face<'a>;
+
+    fn next(&mut self) -> Option<Face<'a>> {
+        let face = self.faces.get(self.i)?;
+        self.i += 1;
+        Some(face)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.faces.len() - self.i;
+        (len,Some(len))
+    }
+}
+
+impl<'a> ExactSizeIterator for FacesIter<'a> {}
+
+impl<'a> IntoIterator for &'a Faces {
+    type Item = Face<'a>;
+    type IntoIter = FacesIter<'a>;
+
+    fn into_iter(self) -> FacesIter<'a> {
+        self.iter()
+    }
+}
+
+// Faces given as lists of `(v,vt,vn)`, for tests.
+#[cfg(test)]
+#[allow(clippy::type_complexity)]
+pub fn faces(faces : Vec<Vec<(usize,Option<usize>,Option<usize>)>>) -> Faces {
+    faces.into_iter().map(|face| face.into_iter().map(FaceVertex::from)).collect()
+}
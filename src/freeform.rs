@@ -0,0 +1,864 @@
+use std::io;
+use obj::{Float,ObjData,LoadingError,ErrorContext,MaxIndex,MaxIndexes,SourceLine,write_index};
+use reader::{Statement,Direction};
+use visitor::ObjVisitor;
+
+/// Type of a free-form curve or surface given by `cstype`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum CurveType {
+    BasisMatrix,
+    Bezier,
+    BSpline,
+    Cardinal,
+    Taylor,
+}
+
+/// Approximation technique given by `ctech` or `stech`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Technique {
+    /// Constant parametric subdivision with resolutions `(u,v)`,
+    /// given by `ctech cparm res` or `stech cparma ures vres`.
+    Parametric(f32,f32),
+    /// Constant parametric subdivision of the trimmed surface given by `stech cparmb uvres`.
+    ParametricTrimmed(f32),
+    /// Constant spatial subdivision given by `cspace maxlength`.
+    Spatial(f32),
+    /// Curvature dependent subdivision given by `curv maxdist maxangle`.
+    /// The angle is in degrees.
+    Curvature(f32,f32),
+}
+
+/// Attributes of a free-form curve or surface.
+///
+/// They are given by `cstype`, `deg`, `bmat`, `step`, `ctech` and `stech`
+/// before the element and apply to every following element.
+#[derive(PartialEq, Debug, Clone)]
+pub struct FreeFormAttributes {
+    /// Type of the element.
+    pub curve_type : CurveType,
+    /// Whether the element is rational, i.e. uses the weight of its control points.
+    pub rational : bool,
+    /// Degree `(u,v)` of the element, v is 0 for curves.
+    pub degree : (usize,usize),
+    /// Basis matrices `(u,v)` given by `bmat`, empty if not given.
+    pub basis_matrix : (Vec<Float>,Vec<Float>),
+    /// Step sizes `(u,v)` given by `step`, 0 if not given.
+    pub step : (usize,usize),
+    /// Approximation technique of curves given by `ctech`.
+    pub curve_technique : Option<Technique>,
+    /// Approximation technique of surfaces given by `stech`.
+    pub surface_technique : Option<Technique>,
+}
+
+/// A reference to a part of a curve in parameter space.
+///
+/// Used by `trim`, `hole` and `scrv` statements.
+#[derive(PartialEq, Debug, Clone)]
+pub struct CurveRef {
+    /// Starting and ending parameters `(u0,u1)` on the curve.
+    pub range : (Float,Float),
+    /// Index of the curve in `curves2`.
+    pub curve : usize,
+}
+
+/// A free-form curve given by `curv`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Curve {
+    pub attributes : FreeFormAttributes,
+    /// Starting and ending parameters `(u0,u1)` of the curve.
+    pub range : (Float,Float),
+    /// Indices of the control vertices in `vertices`.
+    pub control_points : Vec<usize>,
+    /// Parameter vector given by `parm u`.
+    pub parameters : Vec<Float>,
+    /// Indices of the special points in `parameter_vertices` given by `sp`.
+    pub special_points : Vec<usize>,
+}
+
+/// A free-form curve in parameter space given by `curv2`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Curve2 {
+    pub attributes : FreeFormAttributes,
+    /// Indices of the control points in `parameter_vertices`.
+    pub control_points : Vec<usize>,
+    /// Parameter vector given by `parm u`.
+    pub parameters : Vec<Float>,
+    /// Indices of the special points in `parameter_vertices` given by `sp`.
+    pub special_points : Vec<usize>,
+}
+
+/// A free-form surface given by `surf`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Surface {
+    pub attributes : FreeFormAttributes,
+    /// Starting and ending parameters `(s0,s1)` in the u direction.
+    pub range_u : (Float,Float),
+    /// Starting and ending parameters `(t0,t1)` in the v direction.
+    pub range_v : (Float,Float),
+    /// Control vertices `(v,vt,vn)` like the corners of a face.
+    pub control_points : Vec<(usize,Option<usize>,Option<usize>)>,
+    /// Parameter vector given by `parm u`.
+    pub parameters_u : Vec<Float>,
+    /// Parameter vector given by `parm v`.
+    pub parameters_v : Vec<Float>,
+    /// Outer trimming loops given by `trim`.
+    pub trims : Vec<Vec<CurveRef>>,
+    /// Inner trimming loops given by `hole`.
+    pub holes : Vec<Vec<CurveRef>>,
+    /// Special curves given by `scrv`.
+    pub special_curves : Vec<Vec<CurveRef>>,
+    /// Indices of the special points in `parameter_vertices` given by `sp`.
+    pub special_points : Vec<usize>,
+}
+
+// Element whose body (`parm`, `trim`, ...) is being parsed.
+enum Element {
+    Curve(Curve),
+    Curve2(Curve2),
+    Surface(Surface),
+}
+
+// State of the free-form statements while loading a file.
+pub struct FreeFormParser {
+    curve_type : Option<(CurveType,bool)>,
+    degree : (usize,usize),
+    basis_matrix : (Vec<Float>,Vec<Float>),
+    step : (usize,usize),
+    curve_technique : Option<Technique>,
+    surface_technique : Option<Technique>,
+    element : Option<Element>,
+    // Context of the statement starting the element, to report a missing `end`.
+    element_start : Option<ErrorContext>,
+}
+
+impl CurveType {
+    /// Curve type written as `s` in `cstype`.
+    pub fn from_keyword(s : &str) -> Option<CurveType> {
+        match s {
+            "bmatrix" => Some(CurveType::BasisMatrix),
+            "bezier" => Some(CurveType::Bezier),
+            "bspline" => Some(CurveType::BSpline),
+            "cardinal" => Some(CurveType::Cardinal),
+            "taylor" => Some(CurveType::Taylor),
+            _ => None,
+        }
+    }
+
+    /// Keyword of the curve type in `cstype`.
+    pub fn keyword(&self) -> &'static str {
+        match *self {
+            CurveType::BasisMatrix => "bmatrix",
+            CurveType::Bezier => "bezier",
+            CurveType::BSpline => "bspline",
+            CurveType::Cardinal => "cardinal",
+            CurveType::Taylor => "taylor",
+        }
+    }
+}
+
+impl FreeFormAttributes {
+    pub fn new(curve_type : CurveType) -> FreeFormAttributes {
+        FreeFormAttributes {
+            curve_type,
+            rational : false,
+            degree : (0,0),
+            basis_matrix : (Vec::new(),Vec::new()),
+            step : (0,0),
+            curve_technique : None,
+            surface_technique : None,
+        }
+    }
+}
+
+impl Curve {
+    pub fn new(attributes : FreeFormAttributes, range : (Float,Float)) -> Curve {
+        Curve {
+            attributes,
+            range,
+            control_points : Vec::new(),
+            parameters : Vec::new(),
+            special_points : Vec::new(),
+        }
+    }
+}
+
+impl Curve2 {
+    pub fn new(attributes : FreeFormAttributes) -> Curve2 {
+        Curve2 {
+            attributes,
+            control_points : Vec::new(),
+            parameters : Vec::new(),
+            special_points : Vec::new(),
+        }
+    }
+}
+
+impl Surface {
+    pub fn new(attributes : FreeFormAttributes, range_u : (Float,Float), range_v : (Float,Float)) -> Surface {
+        Surface {
+            attributes,
+            range_u,
+            range_v,
+            control_points : Vec::new(),
+            parameters_u : Vec::new(),
+            parameters_v : Vec::new(),
+            trims : Vec::new(),
+            holes : Vec::new(),
+            special_curves : Vec::new(),
+            special_points : Vec::new(),
+        }
+    }
+}
+
+impl FreeFormParser {
+    pub fn new() -> FreeFormParser {
+        FreeFormParser {
+            curve_type : None,
+            degree : (0,0),
+            basis_matrix : (Vec::new(),Vec::new()),
+            step : (0,0),
+            curve_technique : None,
+            surface_technique : None,
+            element : None,
+            element_start : None,
+        }
+    }
+
+    fn attributes(&self, line : &SourceLine) -> Result<FreeFormAttributes, LoadingError> {
+        let (curve_type,rational) = match self.curve_type {
+            Some(t) => t,
+            None => return Err(line.invalid(line.keyword(),"a `cstype` statement before the element")),
+        };
+        Ok(FreeFormAttributes {
+            curve_type,
+            rational,
+            degree : self.degree,
+            basis_matrix : self.basis_matrix.clone(),
+            step : self.step,
+            curve_technique : self.curve_technique,
+            surface_technique : self.surface_technique,
+        })
+    }
+
+    fn start(&mut self, element : Element, line : &SourceLine) -> Result<(), LoadingError> {
+        // Elements can't be nested
+        if self.element.is_some() {
+            return Err(line.invalid(line.keyword(),"`end` before a new element"));
+        }
+        self.element = Some(element);
+        self.element_start = Some(line.context(line.keyword(),"`end` closing this element"));
+        Ok(())
+    }
+
+    // Surface whose body is being parsed.
+    fn surface(&mut self, line : &SourceLine) -> Result<&mut Surface, LoadingError> {
+        match self.element {
+            Some(Element::Surface(ref mut s)) => Ok(s),
+            _ => Err(line.invalid(line.keyword(),"a surface before the statement")),
+        }
+    }
+
+    fn resolve_curve_refs(max : &mut MaxIndexes, refs : Vec<(Float,Float,isize)>, line : &SourceLine)
+        -> Result<Vec<CurveRef>, LoadingError> {
+        let mut vec = Vec::with_capacity(refs.len());
+        for (k,(u0,u1,curve)) in refs.into_iter().enumerate() {
+            let curve = max.curv2.resolve(curve,line,3*k+2,0)?;
+            vec.push(CurveRef { range : (u0,u1), curve });
+        }
+        Ok(vec)
+    }
+
+    // Resolve indices written from the argument `first`.
+    fn resolve_indices(index : &mut MaxIndex, indices : Vec<isize>, line : &SourceLine, first : usize)
+        -> Result<Vec<usize>, LoadingError> {
+        let mut vec = Vec::with_capacity(indices.len());
+        for (k,i) in indices.into_iter().enumerate() {
+            vec.push(index.resolve(i,line,first+k,0)?);
+        }
+        Ok(vec)
+    }
+
+    // Apply a free-form statement, other statements are ignored.
+    // Curves and surfaces are given to `visitor` once closed by `end`.
+    pub fn parse<V : ObjVisitor>(&mut self, visitor : &mut V, max : &mut MaxIndexes, statement : Statement,
+                                 line : &SourceLine) -> Result<(), LoadingError> {
+        match statement {
+            Statement::CurveType(t,rational) => self.curve_type = Some((t,rational)),
+            Statement::Degree(u,v) => self.degree = (u,v),
+            Statement::Step(u,v) => self.step = (u,v),
+            Statement::BasisMatrix(Direction::U,values) => self.basis_matrix.0 = values,
+            Statement::BasisMatrix(Direction::V,values) => self.basis_matrix.1 = values,
+            Statement::CurveTechnique(technique) => self.curve_technique = Some(technique),
+            Statement::SurfaceTechnique(technique) => self.surface_technique = Some(technique),
+            Statement::Curve(range,vertices) => {
+                let mut curve = Curve::new(self.attributes(line)?,range);
+                curve.control_points = FreeFormParser::resolve_indices(&mut max.v,vertices,line,2)?;
+                self.start(Element::Curve(curve),line)?;
+            },
+            Statement::Curve2(vertices) => {
+                let mut curve = Curve2::new(self.attributes(line)?);
+                curve.control_points = FreeFormParser::resolve_indices(&mut max.vp,vertices,line,0)?;
+                self.start(Element::Curve2(curve),line)?;
+            },
+            Statement::Surface(range_u,range_v,vertices) => {
+                let mut surface = Surface::new(self.attributes(line)?,range_u,range_v);
+                for (k,vertex) in vertices.into_iter().enumerate() {
+                    surface.control_points.push(max.resolve_vertex_ref(vertex,line,k+4)?);
+                }
+                self.start(Element::Surface(surface),line)?;
+            },
+            Statement::Parameters(direction,values) => {
+                match (&mut self.element,direction) {
+                    (&mut Some(Element::Curve(ref mut c)),Direction::U) => c.parameters = values,
+                    (&mut Some(Element::Curve2(ref mut c)),Direction::U) => c.parameters = values,
+                    (&mut Some(Element::Surface(ref mut s)),Direction::U) => s.parameters_u = values,
+                    (&mut Some(Element::Surface(ref mut s)),Direction::V) => s.parameters_v = values,
+                    (&mut Some(_),_) => return Err(line.parse_error(line.argument(0),"`u`")),
+                    (&mut None,_) => return Err(line.invalid(line.keyword(),"a curve or surface before `parm`")),
+                }
+            },
+            Statement::Trim(refs) => {
+                self.surface(line)?.trims.push(FreeFormParser::resolve_curve_refs(max,refs,line)?);
+            },
+            Statement::Hole(refs) => {
+                self.surface(line)?.holes.push(FreeFormParser::resolve_curve_refs(max,refs,line)?);
+            },
+            Statement::SpecialCurve(refs) => {
+                self.surface(line)?.special_curves.push(FreeFormParser::resolve_curve_refs(max,refs,line)?);
+            },
+            Statement::SpecialPoints(vertices) => {
+                let points = FreeFormParser::resolve_indices(&mut max.vp,vertices,line,0)?;
+                match self.element {
+                    Some(Element::Curve(ref mut c)) => c.special_points.extend(points),
+                    Some(Element::Curve2(ref mut c)) => c.special_points.extend(points),
+                    Some(Element::Surface(ref mut s)) => s.special_points.extend(points),
+                    None => return Err(line.invalid(line.keyword(),"a curve or surface before `sp`")),
+                }
+            },
+            Statement::End => {
+                match self.element.take() {
+                    Some(Element::Curve(c)) => visitor.curve(c),
+                    Some(Element::Curve2(c)) => {
+                        visitor.curve2(c);
+                        max.curv2.len += 1;
+                    },
+                    Some(Element::Surface(s)) => visitor.surface(s),
+                    None => return Err(line.invalid(line.keyword(),"a curve or surface before `end`")),
+                }
+                self.element_start = None;
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    // Check that the last element has been closed by `end`.
+    pub fn finish(&self) -> Result<(), LoadingError> {
+        match self.element_start {
+            Some(ref context) => Err(LoadingError::InvalidLine(context.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+// Write the attributes which differ from the active ones.
+// `bmat`, `step`, `ctech` and `stech` can't be reset, so the active values are kept
+// when an element has none.
+fn write_attributes<W : io::Write>(output : &mut W, actif : &mut Option<FreeFormAttributes>,
+                                   attributes : &FreeFormAttributes) -> Result<(), LoadingError> {
+    let first = actif.is_none();
+    let previous = actif.take().unwrap_or_else(|| attributes.clone());
+    let mut written = attributes.clone();
+    if first || previous.curve_type != attributes.curve_type || previous.rational != attributes.rational {
+        let rat = if attributes.rational {"rat "} else {""};
+        let line : String = format!("cstype {}{}\n",rat,attributes.curve_type.keyword());
+        output.write_all(line.as_bytes())?;
+    }
+    if first || previous.degree != attributes.degree {
+        output.write_all(format!("deg {}",attributes.degree.0).as_bytes())?;
+        if attributes.degree.1 != 0 {
+            output.write_all(format!(" {}",attributes.degree.1).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+    }
+    for &(dir,previous,matrix) in &[("u",&previous.basis_matrix.0,&attributes.basis_matrix.0),
+                                    ("v",&previous.basis_matrix.1,&attributes.basis_matrix.1)] {
+        if !matrix.is_empty() && (first || previous != matrix) {
+            output.write_all(format!("bmat {}",dir).as_bytes())?;
+            write_values(output,matrix)?;
+        }
+    }
+    if attributes.basis_matrix.0.is_empty() {
+        written.basis_matrix.0 = previous.basis_matrix.0.clone();
+    }
+    if attributes.basis_matrix.1.is_empty() {
+        written.basis_matrix.1 = previous.basis_matrix.1.clone();
+    }
+    if attributes.step == (0,0) {
+        written.step = previous.step;
+    } else if first || previous.step != attributes.step {
+        output.write_all(format!("step {}",attributes.step.0).as_bytes())?;
+        if attributes.step.1 != 0 {
+            output.write_all(format!(" {}",attributes.step.1).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+    }
+    match attributes.curve_technique {
+        None => written.curve_technique = previous.curve_technique,
+        Some(technique) if first || previous.curve_technique != attributes.curve_technique => {
+            let line : String = match technique {
+                Technique::Parametric(res,_) | Technique::ParametricTrimmed(res) => format!("ctech cparm {}\n",res),
+                Technique::Spatial(length) => format!("ctech cspace {}\n",length),
+                Technique::Curvature(dist,angle) => format!("ctech curv {} {}\n",dist,angle),
+            };
+            output.write_all(line.as_bytes())?;
+        },
+        Some(_) => {},
+    }
+    match attributes.surface_technique {
+        None => written.surface_technique = previous.surface_technique,
+        Some(technique) if first || previous.surface_technique != attributes.surface_technique => {
+            let line : String = match technique {
+                Technique::Parametric(ures,vres) => format!("stech cparma {} {}\n",ures,vres),
+                Technique::ParametricTrimmed(res) => format!("stech cparmb {}\n",res),
+                Technique::Spatial(length) => format!("stech cspace {}\n",length),
+                Technique::Curvature(dist,angle) => format!("stech curv {} {}\n",dist,angle),
+            };
+            output.write_all(line.as_bytes())?;
+        },
+        Some(_) => {},
+    }
+    *actif = Some(written);
+    Ok(())
+}
+
+// Write a list of values followed by a new line.
+fn write_values<W : io::Write>(output : &mut W, values : &[Float]) -> Result<(), LoadingError> {
+    for value in values {
+        output.write_all(format!(" {}",value).as_bytes())?;
+    }
+    output.write_all("\n".as_bytes())?;
+    Ok(())
+}
+
+fn write_special_points<W : io::Write>(data : &ObjData, output : &mut W, points : &[usize],
+                                       relative : bool) -> Result<(), LoadingError> {
+    if !points.is_empty() {
+        output.write_all("sp".as_bytes())?;
+        for &vp in points {
+            output.write_all(format!(" {}",write_index(vp,data.parameter_vertices.len(),relative)).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+    }
+    Ok(())
+}
+
+// State of the free-form statements while writing a file.
+pub struct FreeFormWriter {
+    actif : Option<FreeFormAttributes>,
+    // Position in the written file of each curve of `curves2`.
+    positions : Vec<usize>,
+    // Number of curves of `curves2` already written.
+    written : usize,
+}
+
+impl FreeFormWriter {
+    // `curves2` gives the indices of the curves of `curves2` in the order in which they are written.
+    pub fn new<I : Iterator<Item=usize>>(len : usize, curves2 : I) -> FreeFormWriter {
+        let mut positions : Vec<usize> = (0..len).collect();
+        for (k,i) in curves2.enumerate() {
+            if i < len {
+                positions[i] = k;
+            }
+        }
+        FreeFormWriter {
+            actif : None,
+            positions,
+            written : 0,
+        }
+    }
+
+    fn write_curve_refs<W : io::Write>(&self, output : &mut W, statement : &str,
+                                       loops : &[Vec<CurveRef>], relative : bool) -> Result<(), LoadingError> {
+        for refs in loops {
+            output.write_all(statement.as_bytes())?;
+            for r in refs {
+                // Curves written later can only be referenced by an absolute index
+                let position = self.positions[r.curve];
+                let arg : String = format!(" {} {} {}",r.range.0,r.range.1,
+                                           write_index(position,self.written,relative && position < self.written));
+                output.write_all(arg.as_bytes())?;
+            }
+            output.write_all("\n".as_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Write the curve `i` of `curves`.
+    pub fn curve<W : io::Write>(&mut self, data : &ObjData, output : &mut W, i : usize, relative : bool) -> Result<(), LoadingError> {
+        let curve = &data.curves[i];
+        write_attributes(output,&mut self.actif,&curve.attributes)?;
+        output.write_all(format!("curv {} {}",curve.range.0,curve.range.1).as_bytes())?;
+        for &v in &curve.control_points {
+            output.write_all(format!(" {}",write_index(v,data.vertices.len(),relative)).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+        if !curve.parameters.is_empty() {
+            output.write_all("parm u".as_bytes())?;
+            write_values(output,&curve.parameters)?;
+        }
+        write_special_points(data,output,&curve.special_points,relative)?;
+        output.write_all("end\n".as_bytes())?;
+        Ok(())
+    }
+
+    // Write the curve `i` of `curves2`.
+    pub fn curve2<W : io::Write>(&mut self, data : &ObjData, output : &mut W, i : usize, relative : bool) -> Result<(), LoadingError> {
+        let curve = &data.curves2[i];
+        write_attributes(output,&mut self.actif,&curve.attributes)?;
+        output.write_all("curv2".as_bytes())?;
+        for &vp in &curve.control_points {
+            output.write_all(format!(" {}",write_index(vp,data.parameter_vertices.len(),relative)).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+        if !curve.parameters.is_empty() {
+            output.write_all("parm u".as_bytes())?;
+            write_values(output,&curve.parameters)?;
+        }
+        write_special_points(data,output,&curve.special_points,relative)?;
+        output.write_all("end\n".as_bytes())?;
+        self.written += 1;
+        Ok(())
+    }
+
+    // Write the surface `i` of `surfaces`.
+    pub fn surface<W : io::Write>(&mut self, data : &ObjData, output : &mut W, i : usize, relative : bool) -> Result<(), LoadingError> {
+        let surface = &data.surfaces[i];
+        write_attributes(output,&mut self.actif,&surface.attributes)?;
+        let line : String = format!("surf {} {} {} {}",surface.range_u.0,surface.range_u.1,
+                                    surface.range_v.0,surface.range_v.1);
+        output.write_all(line.as_bytes())?;
+        for &(v,vt,vn) in &surface.control_points {
+            let v_str = write_index(v,data.vertices.len(),relative);
+            let vt_str = match vt {
+                Some(val) => write_index(val,data.texcoords.len(),relative),
+                None => "".to_string(),
+            };
+            let vn_str = match vn {
+                Some(val) => write_index(val,data.normals.len(),relative),
+                None => "".to_string(),
+            };
+            output.write_all(format!(" {}/{}/{}",v_str,vt_str,vn_str).as_bytes())?;
+        }
+        output.write_all("\n".as_bytes())?;
+        if !surface.parameters_u.is_empty() {
+            output.write_all("parm u".as_bytes())?;
+            write_values(output,&surface.parameters_u)?;
+        }
+        if !surface.parameters_v.is_empty() {
+            output.write_all("parm v".as_bytes())?;
+            write_values(output,&surface.parameters_v)?;
+        }
+        self.write_curve_refs(output,"trim",&surface.trims,relative)?;
+        self.write_curve_refs(output,"hole",&surface.holes,relative)?;
+        self.write_curve_refs(output,"scrv",&surface.special_curves,relative)?;
+        write_special_points(data,output,&surface.special_points,relative)?;
+        output.write_all("end\n".as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use std::io::BufWriter;
+    use std::str;
+    use obj::*;
+    use obj::Element;
+    use freeform::*;
+
+    const SURFACE : &str =
+    r#"v 0 0 0
+v 1 0 0
+v 0 1 0
+v 1 1 1 2
+vp 0.2 0.2
+vp 0.8 0.2
+vp 0.5 0.8
+vp 0.5
+cstype rat bspline
+deg 1 1
+surf 0 1 0 1 1 2 3 4
+parm u 0 0 1 1
+parm v 0 0 1 1
+trim 0 1 1 0 1 2
+sp 4
+end
+cstype bezier
+deg 2
+curv2 1 2 3 1
+parm u 0 1
+end
+curv2 -4 -3
+parm u 0 1
+end
+bmat u 1 0 0 1
+step 1
+curv 0 1 1 2 3
+parm u 0 1
+end"#;
+
+    #[test]
+    fn load_freeform() {
+        let mut input = BufReader::new(SURFACE.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![(0.2,0.2,1.),(0.8,0.2,1.),(0.5,0.8,1.),(0.5,0.,1.)],data.parameter_vertices);
+
+        let mut attributes = FreeFormAttributes::new(CurveType::BSpline);
+        attributes.rational = true;
+        attributes.degree = (1,1);
+        let mut surface = Surface::new(attributes,(0.,1.),(0.,1.));
+        surface.control_points = vec![(0,None,None),(1,None,None),(2,None,None),(3,None,None)];
+        surface.parameters_u = vec![0.,0.,1.,1.];
+        surface.parameters_v = vec![0.,0.,1.,1.];
+        surface.trims = vec![vec![CurveRef { range : (0.,1.), curve : 0 },CurveRef { range : (0.,1.), curve : 1 }]];
+        surface.special_points = vec![3];
+        assert_eq!(vec![surface],data.surfaces);
+
+        let mut attributes = FreeFormAttributes::new(CurveType::Bezier);
+        attributes.degree = (2,0);
+        let mut curve1 = Curve2::new(attributes.clone());
+        curve1.control_points = vec![0,1,2,0];
+        curve1.parameters = vec![0.,1.];
+        let mut curve2 = Curve2::new(attributes.clone());
+        curve2.control_points = vec![0,1];
+        curve2.parameters = vec![0.,1.];
+        assert_eq!(vec![curve1,curve2],data.curves2);
+
+        attributes.basis_matrix.0 = vec![1.,0.,0.,1.];
+        attributes.step = (1,0);
+        let mut curve = Curve::new(attributes,(0.,1.));
+        curve.control_points = vec![0,1,2];
+        curve.parameters = vec![0.,1.];
+        assert_eq!(vec![curve],data.curves);
+        assert_eq!(vec![Element::Surface(0),Element::Curve2(0),Element::Curve2(1),Element::Curve(0)],
+                   data.objects[0].elements);
+    }
+
+    #[test]
+    fn load_freeform_invalid_line() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        curv 0 1 1 2
+        end"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(context) => assert!(context.line == 3),
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        cstype bspline
+        deg 1
+        curv 0 1 1 2
+        parm u 0 0 1 1"#;
+
+        // A missing `end` is reported at the start of the element
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(context) => {
+                assert_eq!((5,"curv"),(context.line,context.token.as_str()));
+            },
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"vp 0 0
+        trim 0 1 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_freeform_wrong_number_of_arguments() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        cstype bspline
+        deg 1
+        surf 0 1 0 1
+        end"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 5),
+            _ => panic!(),
+        };
+
+        // A basis matrix needs values
+        match ObjData::from_str("cstype bmatrix\nbmat u\n").err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_freeform_index_out_of_range() {
+        let obj_str =
+        r#"vp 0 0
+        vp 1 0
+        cstype bspline
+        deg 1
+        curv2 1 2
+        end
+        surf 0 1 0 1 1 2 3 4
+        trim 0 1 2
+        end"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((7,IndexKind::Vertex,4),(context.line,kind,index));
+            },
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_techniques() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        cstype bspline
+        deg 1
+        ctech cspace 0.5
+        stech cparma 2 3
+        curv 0 1 1 2
+        parm u 0 0 1 1
+        end
+        ctech curv 0.1 10
+        stech cparmb 4
+        curv 0 1 1 2
+        parm u 0 0 1 1
+        end"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(Some(Technique::Spatial(0.5)),data.curves[0].attributes.curve_technique);
+        assert_eq!(Some(Technique::Parametric(2.,3.)),data.curves[0].attributes.surface_technique);
+        assert_eq!(Some(Technique::Curvature(0.1,10.)),data.curves[1].attributes.curve_technique);
+        assert_eq!(Some(Technique::ParametricTrimmed(4.)),data.curves[1].attributes.surface_technique);
+
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        let mut input = BufReader::new(&buf[..]);
+        let reload = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.curves,reload.curves);
+
+        let obj_str =
+        r#"ctech cparma 2 3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(context) => assert!(context.line == 1),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn write_freeform() {
+        let expected =
+        r#"v 0 0 0 1
+v 1 0 0 1
+v 0 1 0 1
+v 1 1 1 2
+vp 0.2 0.2 1
+vp 0.8 0.2 1
+vp 0.5 0.8 1
+vp 0.5 0 1
+cstype rat bspline
+deg 1 1
+surf 0 1 0 1 1// 2// 3// 4//
+parm u 0 0 1 1
+parm v 0 0 1 1
+trim 0 1 1 0 1 2
+sp 4
+end
+cstype bezier
+deg 2
+curv2 1 2 3 1
+parm u 0 1
+end
+curv2 1 2
+parm u 0 1
+end
+bmat u 1 0 0 1
+step 1
+curv 0 1 1 2 3
+parm u 0 1
+end
+"#;
+        let mut input = BufReader::new(SURFACE.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+
+        let mut input = BufReader::new(&buf[..]);
+        let reload = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(data.parameter_vertices,reload.parameter_vertices);
+        assert_eq!(data.curves,reload.curves);
+        assert_eq!(data.curves2,reload.curves2);
+        assert_eq!(data.surfaces,reload.surfaces);
+        assert_eq!(data.objects,reload.objects);
+    }
+
+    #[test]
+    fn write_freeform_attributes() {
+        // `bmat` and `step` can't be reset so they are not written for the surface
+        let mut data = ObjData::from_str(SURFACE).ok().unwrap();
+        data.objects[0].elements.rotate_left(1);
+        let expected =
+        r#"cstype bezier
+deg 2
+curv2 -4 -3 -2 -4
+parm u 0 1
+end
+curv2 -4 -3
+parm u 0 1
+end
+bmat u 1 0 0 1
+step 1
+curv 0 1 -4 -3 -2
+parm u 0 1
+end
+cstype rat bspline
+deg 1 1
+surf 0 1 0 1 -4// -3// -2// -1//
+parm u 0 0 1 1
+parm v 0 0 1 1
+trim 0 1 -2 0 1 -1
+sp -1
+end
+"#;
+        let mut output = Vec::new();
+        let options = WriteOptions { relative_indices : true };
+        assert!(data.write_with_options(&mut output,&options).is_ok());
+        let written = str::from_utf8(&output).unwrap();
+        assert!(written.ends_with(expected));
+        let reload = ObjData::from_str(written).ok().unwrap();
+        assert_eq!(data.curves,reload.curves);
+        assert_eq!(data.curves2,reload.curves2);
+        assert_eq!(data.surfaces[0].trims,reload.surfaces[0].trims);
+    }
+}
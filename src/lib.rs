@@ -1,8 +1,66 @@
 mod obj;
+mod mtl;
+mod freeform;
+mod tessellate;
+mod visitor;
+mod reader;
+mod bytes;
+mod parallel;
+mod face;
+mod triangulate;
+mod normals;
+mod tangents;
+mod mesh;
+mod stats;
+mod transform;
+pub use obj::Float;
 pub use obj::LoadingError;
+pub use obj::ErrorContext;
+pub use obj::IndexKind;
 pub use obj::ObjData;
 pub use obj::Object;
+pub use obj::Element;
 pub use obj::Group;
+pub use obj::WriteOptions;
+pub use obj::LoadOptions;
+pub use obj::UnknownStatement;
+pub use face::VertexIndex;
+pub use face::TexCoordIndex;
+pub use face::NormalIndex;
+pub use face::FaceVertex;
+pub use face::Face;
+pub use face::Faces;
+pub use face::FacesIter;
+pub use mtl::MtlData;
+pub use mtl::Material;
+pub use mtl::TextureMap;
+pub use freeform::CurveType;
+pub use freeform::FreeFormAttributes;
+pub use freeform::Technique;
+pub use freeform::CurveRef;
+pub use freeform::Curve;
+pub use freeform::Curve2;
+pub use freeform::Surface;
+pub use tessellate::TessellationError;
+pub use tessellate::SurfaceMesh;
+pub use tessellate::DEFAULT_RESOLUTION;
+pub use normals::NormalMode;
+pub use tangents::TangentError;
+pub use tangents::Tangent;
+pub use mesh::MeshFaces;
+pub use mesh::MeshVertex;
+pub use mesh::MeshIndices;
+pub use mesh::IndexedMesh;
+pub use stats::Bounds;
+pub use stats::MeshStats;
+pub use visitor::ObjVisitor;
+pub use visitor::visit;
+pub use reader::Direction;
+pub use reader::Statement;
+pub use reader::ObjReader;
+pub use reader::ObjWriter;
+pub use bytes::visit_bytes;
+pub use parallel::visit_bytes_parallel;
 
 #[cfg(test)]
 mod test;
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use obj::{Float,ObjData,float_key};
+use tangents::{Tangent,TangentError,tangent_triangles};
+
+/// Faces converted by `ObjData::to_indexed_mesh`.
+///
+/// An object or a group which doesn't exist has no faces.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum MeshFaces {
+    /// Every face.
+    All,
+    /// Faces of the object `i` of `objects`.
+    Object(usize),
+    /// Faces of the group `i` of `groups`.
+    Group(usize),
+    /// Faces with the material `i` of `materials`, or faces without material for `None`.
+    Material(Option<usize>),
+}
+
+/// Vertex of an `IndexedMesh`, laid out to be uploaded to a GPU as is.
+#[repr(C)]
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct MeshVertex {
+    pub position : [Float;3],
+    /// Texture coordinates `(u,v)`, `(0,0)` for vertices without some.
+    pub texcoord : [Float;2],
+    /// Normal, `(0,0,0)` for vertices without one.
+    pub normal : [Float;3],
+}
+
+/// Indices of the vertices of an `IndexedMesh`.
+#[derive(PartialEq, Debug, Clone)]
+pub enum MeshIndices {
+    /// Indices of meshes of at most 65536 vertices.
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+/// Triangles with one vertex for every distinct `(v,vt,vn)` of their faces.
+#[derive(PartialEq, Debug, Clone)]
+pub struct IndexedMesh {
+    pub vertices : Vec<MeshVertex>,
+    /// Tangent `(x,y,z)` and sign of the bitangent of every vertex,
+    /// only given by `ObjData::to_indexed_mesh_with_tangents`.
+    pub tangents : Vec<[Float;4]>,
+    /// Indices in `vertices` of the 3 vertices of every triangle.
+    pub indices : MeshIndices,
+}
+
+impl MeshIndices {
+    /// Number of indices.
+    pub fn len(&self) -> usize {
+        match *self {
+            MeshIndices::U16(ref indices) => indices.len(),
+            MeshIndices::U32(ref indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Index `i`, `None` if there are not enough indices.
+    pub fn get(&self, i : usize) -> Option<u32> {
+        match *self {
+            MeshIndices::U16(ref indices) => indices.get(i).map(|&index| u32::from(index)),
+            MeshIndices::U32(ref indices) => indices.get(i).cloned(),
+        }
+    }
+}
+
+impl ObjData {
+    /// Convert `faces` into triangles indexing vertices with their position, texture coordinates
+    /// and normal, for drawing on a GPU.
+    ///
+    /// Vertices of faces with the same `(v,vt,vn)` are the same vertex of the mesh, in the order
+    /// in which they appear in faces. Faces are split by `triangulate_face`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{ObjData,MeshFaces,MeshIndices};
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvn 0 0 1\n\
+    ///                o A\nf 1//1 2//1 3//1 4//1\no B\nf 1 2 3\n";
+    /// let data = ObjData::from_str(obj_str).ok().unwrap();
+    /// let mesh = data.to_indexed_mesh(MeshFaces::Object(0));
+    /// assert_eq!(4,mesh.vertices.len());
+    /// assert_eq!([1.,0.,0.],mesh.vertices[1].position);
+    /// assert_eq!([0.,0.,1.],mesh.vertices[1].normal);
+    /// assert_eq!(MeshIndices::U16(vec![0,1,2,0,2,3]),mesh.indices);
+    ///
+    /// // The vertices of the second object have no normal
+    /// let mesh = data.to_indexed_mesh(MeshFaces::All);
+    /// assert_eq!(7,mesh.vertices.len());
+    /// ```
+    pub fn to_indexed_mesh(&self, faces : MeshFaces) -> IndexedMesh {
+        self.indexed_mesh(faces,None)
+    }
+
+    /// Convert `faces` like `to_indexed_mesh`, adding the tangents given by `compute_tangents`.
+    ///
+    /// Vertices with different tangents are different vertices of the mesh, and quads are split
+    /// along the same diagonal as MikkTSpace. Tangents are computed from every face, not only
+    /// from `faces`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{ObjData,MeshFaces};
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+    ///                f 1/1/1 2/2/1 3/3/1 4/4/1\n";
+    /// let data = ObjData::from_str(obj_str).ok().unwrap();
+    /// let mesh = data.to_indexed_mesh_with_tangents(MeshFaces::All).ok().unwrap();
+    /// assert_eq!(4,mesh.tangents.len());
+    /// assert_eq!([1.,0.,0.,1.],mesh.tangents[0]);
+    /// ```
+    pub fn to_indexed_mesh_with_tangents(&self, faces : MeshFaces) -> Result<IndexedMesh,TangentError> {
+        let tangents = self.compute_tangents()?;
+        Ok(self.indexed_mesh(faces,Some(&tangents)))
+    }
+
+    fn indexed_mesh(&self, faces : MeshFaces, tangents : Option<&[Tangent]>) -> IndexedMesh {
+        let faces : Vec<usize> = match faces {
+            MeshFaces::All => (0..self.faces.len()).collect(),
+            MeshFaces::Object(i) => self.objects.get(i).map_or(Vec::new(),|o| o.primitives.clone()),
+            MeshFaces::Group(i) => self.groups.get(i).map_or(Vec::new(),|g| g.indexes.clone()),
+            MeshFaces::Material(m) => (0..self.faces.len())
+                .filter(|&f| self.face_materials.get(f).cloned().unwrap_or(None) == m).collect(),
+        };
+        let offsets = self.faces.offsets();
+        let mut mesh = IndexedMesh {
+            vertices : Vec::new(),
+            tangents : Vec::new(),
+            indices : MeshIndices::U32(Vec::new()),
+        };
+        let mut indices : Vec<u32> = Vec::new();
+        let mut known = HashMap::new();
+        for f in faces {
+            let face = self.faces.get(f).unwrap();
+            let triangles = match tangents {
+                Some(_) => tangent_triangles(self,face),
+                None => self.triangulate_face(face),
+            };
+            for k in triangles.iter().flat_map(|t| t.iter().cloned()) {
+                let vertex = face.vertices[k];
+                let tangent = tangents.map(|tangents| {
+                    let t = tangents[offsets[f]+k];
+                    [t.tangent.0,t.tangent.1,t.tangent.2,t.sign]
+                });
+                let key = (vertex,tangent.map(|t| t.map(float_key)));
+                let len = mesh.vertices.len() as u32;
+                let index = *known.entry(key).or_insert(len);
+                if index == len {
+                    mesh.vertices.push(MeshVertex {
+                        position : [self[vertex.position].0,self[vertex.position].1,self[vertex.position].2],
+                        texcoord : vertex.texcoord.map(|i| [self[i].0,self[i].1]).unwrap_or([0.;2]),
+                        normal : vertex.normal.map(|i| [self[i].0,self[i].1,self[i].2]).unwrap_or([0.;3]),
+                    });
+                    mesh.tangents.extend(tangent);
+                }
+                indices.push(index);
+            }
+        }
+        mesh.indices = if mesh.vertices.len() <= usize::from(u16::MAX) + 1 {
+            MeshIndices::U16(indices.iter().map(|&i| i as u16).collect())
+        } else {
+            MeshIndices::U32(indices)
+        };
+        mesh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use mesh::*;
+
+    // Indices of a mesh as u32.
+    fn indices(mesh : &IndexedMesh) -> Vec<u32> {
+        (0..mesh.indices.len()).map(|i| mesh.indices.get(i).unwrap()).collect()
+    }
+
+    #[test]
+    fn indexed_mesh() {
+        let obj_str = "mtllib cube.mtl\nv 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nvt 0 0\nvt 1 1\nvn 0 0 1\nvn 0 -1 0\n\
+                       g gr1\nusemtl Red\nf 1/1/1 2/1/1 3/2/1 4/2/1\ng gr2\nf 1/1/2 2/1/2 5/1/2\n\
+                       usemtl Blue\nf 1/1/1 3/2/1 4/2/1\ng gr1 gr2\nf 1 2 5\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+
+        let mesh = data.to_indexed_mesh(MeshFaces::All);
+        assert_eq!(vec![0,1,2,0,2,3,4,5,6,0,2,3,7,8,9],indices(&mesh));
+        assert_eq!(10,mesh.vertices.len());
+        assert_eq!(MeshVertex { position : [1.,1.,0.], texcoord : [1.,1.], normal : [0.,0.,1.] },mesh.vertices[2]);
+        assert_eq!(MeshVertex { position : [0.,0.,1.], texcoord : [0.,0.], normal : [0.,-1.,0.] },mesh.vertices[6]);
+        assert_eq!(MeshVertex { position : [1.,0.,0.], texcoord : [0.,0.], normal : [0.,0.,0.] },mesh.vertices[8]);
+        assert!(mesh.tangents.is_empty());
+
+        let mesh = data.to_indexed_mesh(MeshFaces::Group(1));
+        assert_eq!(vec![0,1,2,3,4,5,6,7,8],indices(&mesh));
+        assert!(data.to_indexed_mesh(MeshFaces::Group(2)).vertices.is_empty());
+        assert!(data.to_indexed_mesh(MeshFaces::Object(1)).indices.is_empty());
+        let mesh = data.to_indexed_mesh(MeshFaces::Material(Some(1)));
+        assert_eq!(vec![0,1,2,3,4,5],indices(&mesh));
+        assert_eq!(MeshVertex { position : [0.,0.,0.], texcoord : [0.,0.], normal : [0.,0.,0.] },mesh.vertices[3]);
+        let mesh = data.to_indexed_mesh(MeshFaces::Material(None));
+        assert!(mesh.indices.is_empty());
+        let mesh = data.to_indexed_mesh(MeshFaces::Object(0));
+        assert_eq!(15,mesh.indices.len());
+    }
+
+    #[test]
+    fn indexed_mesh_tangents() {
+        // The tangent of the shared vertices depends on the orientation of the texture of each face
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+                       vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+                       f 1/1/1 2/2/1 3/3/1 4/4/1\nf 2/2/1 5/1/1 6/4/1 3/3/1\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        assert_eq!(6,data.to_indexed_mesh(MeshFaces::All).vertices.len());
+        let mesh = data.to_indexed_mesh_with_tangents(MeshFaces::All).ok().unwrap();
+        assert_eq!(8,mesh.vertices.len());
+        assert_eq!(8,mesh.tangents.len());
+        assert_eq!([-1.,0.,0.,-1.],mesh.tangents[7]);
+        assert_eq!(12,mesh.indices.len());
+
+        let data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\n").ok().unwrap();
+        assert!(data.to_indexed_mesh_with_tangents(MeshFaces::All).is_err());
+    }
+
+    #[test]
+    fn indexed_mesh_u32() {
+        let mut obj_str = String::new();
+        for i in 0..70000 {
+            obj_str += &format!("v {} 0 0\n",i);
+        }
+        for i in 0..70000/3 {
+            obj_str += &format!("f {} {} {}\n",3*i+1,3*i+2,3*i+3);
+        }
+        let data = ObjData::from_str(&obj_str).ok().unwrap();
+        let mesh = data.to_indexed_mesh(MeshFaces::All);
+        assert_eq!(69999,mesh.vertices.len());
+        match mesh.indices {
+            MeshIndices::U32(ref indices) => assert_eq!(69998,indices[69998]),
+            _ => panic!(),
+        }
+    }
+}
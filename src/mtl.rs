@@ -0,0 +1,523 @@
+use std::io;
+use std::str::FromStr;
+use std::path::Path;
+use obj::{LoadingError,SourceLine,open,join_args};
+
+/// A texture map statement with its options.
+#[derive(PartialEq, Debug, Clone)]
+pub struct TextureMap {
+    /// Statement of the map (e.g. `map_Kd`, `bump`, `disp`).
+    pub statement : String,
+    /// File name of the texture.
+    pub file : String,
+    /// Origin offset `(u,v,w)` given by `-o`, missing components being 0.
+    pub offset : Option<(f32,f32,f32)>,
+    /// Scale `(u,v,w)` given by `-s`, missing components being 1.
+    pub scale : Option<(f32,f32,f32)>,
+    /// Bump multiplier given by `-bm`.
+    pub bump_multiplier : Option<f32>,
+    /// Clamping given by `-clamp`.
+    pub clamp : Option<bool>,
+    /// Other options with their arguments (e.g. `-blendu off`, `-type sphere`), written back as they are.
+    pub unknown_options : Vec<String>,
+}
+
+/// A material declared by `newmtl`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Material {
+    /// Name of the material.
+    pub name : String,
+    /// Ambient color `(r,g,b)` given by `Ka`.
+    pub ambient : Option<(f32,f32,f32)>,
+    /// Diffuse color `(r,g,b)` given by `Kd`.
+    pub diffuse : Option<(f32,f32,f32)>,
+    /// Specular color `(r,g,b)` given by `Ks`.
+    pub specular : Option<(f32,f32,f32)>,
+    /// Emissive color `(r,g,b)` given by `Ke`.
+    pub emissive : Option<(f32,f32,f32)>,
+    /// Specular exponent given by `Ns`.
+    pub specular_exponent : Option<f32>,
+    /// Optical density given by `Ni`.
+    pub optical_density : Option<f32>,
+    /// Dissolve given by `d`.
+    /// A transparency `Tr` is stored as a dissolve of `1 - Tr`.
+    pub dissolve : Option<f32>,
+    /// Illumination model given by `illum`.
+    pub illumination : Option<u32>,
+    /// List of texture maps.
+    pub maps : Vec<TextureMap>,
+    /// Other statements (e.g. `Tf 1 1 1`, `sharpness 60`), written back as they are.
+    pub unknown_statements : Vec<String>,
+}
+
+/// A struct containing all materials of a material library.
+#[derive(PartialEq, Debug)]
+pub struct MtlData {
+    /// List of materials.
+    pub materials : Vec<Material>,
+}
+
+fn parse<T : FromStr>(s : &str, line : &SourceLine, expected : &str) -> Result<T, LoadingError> {
+    match s.parse::<T>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(line.parse_error(s,expected)),
+    }
+}
+
+fn parse_color(statement : &str, args : &[&str], line : &SourceLine) -> Result<(f32,f32,f32), LoadingError> {
+    let expected = format!("1 or 3 floats for `{}`",statement);
+    if args.len() == 1 {
+        let r = parse::<f32>(args[0],line,&expected)?;
+        Ok((r,r,r))
+    } else if args.len() == 3 {
+        Ok((parse::<f32>(args[0],line,&expected)?,parse::<f32>(args[1],line,&expected)?,parse::<f32>(args[2],line,&expected)?))
+    } else {
+        Err(line.wrong_arguments(statement,&expected))
+    }
+}
+
+fn parse_scalar<T : FromStr>(statement : &str, args : &[&str], line : &SourceLine, kind : &str) -> Result<T, LoadingError> {
+    let expected = format!("{} for `{}`",kind,statement);
+    if args.len() != 1 {
+        return Err(line.wrong_arguments(statement,&expected));
+    }
+    parse::<T>(args[0],line,&expected)
+}
+
+// Parse the 1 to 3 values following an option like `-o` or `-s`, missing ones being `default`.
+fn parse_option_values(option : &str, args : &[&str], i : &mut usize, default : f32, line : &SourceLine) -> Result<(f32,f32,f32), LoadingError> {
+    let mut values : Vec<f32> = Vec::new();
+    while *i < args.len() && values.len() < 3 {
+        match args[*i].parse::<f32>() {
+            Ok(v) => values.push(v),
+            Err(_) => break,
+        }
+        *i += 1;
+    }
+    match values.len() {
+        1 => Ok((values[0],default,default)),
+        2 => Ok((values[0],values[1],default)),
+        3 => Ok((values[0],values[1],values[2])),
+        _ => Err(line.wrong_arguments(option,&format!("1 to 3 floats for `{}`",option))),
+    }
+}
+
+fn parse_map(statement : &str, args : &[&str], line : &SourceLine) -> Result<TextureMap, LoadingError> {
+    let mut map = TextureMap::new(String::from(statement), String::new());
+    let mut i = 0;
+    while i < args.len() && args[i].starts_with('-') {
+        let option = args[i];
+        i += 1;
+        match option {
+            "-o" => map.offset = Some(parse_option_values(option,args,&mut i,0.,line)?),
+            "-s" => map.scale = Some(parse_option_values(option,args,&mut i,1.,line)?),
+            "-bm" => {
+                if i >= args.len() {
+                    return Err(line.wrong_arguments(option,"a float for `-bm`"));
+                }
+                map.bump_multiplier = Some(parse::<f32>(args[i],line,"a float for `-bm`")?);
+                i += 1;
+            },
+            "-clamp" => {
+                if i >= args.len() {
+                    return Err(line.wrong_arguments(option,"`on` or `off` for `-clamp`"));
+                }
+                map.clamp = match args[i] {
+                    "on" => Some(true),
+                    "off" => Some(false),
+                    _ => return Err(line.parse_error(args[i],"`on` or `off` for `-clamp`")),
+                };
+                i += 1;
+            },
+            _ => {
+                // Keep the option with its arguments, leaving the last argument for the file
+                let count = match option {
+                    "-blendu" | "-blendv" | "-cc" | "-boost" | "-imfchan" | "-texres" | "-type" => 1,
+                    "-mm" => 2,
+                    // Like `-t`, take the floats following the option
+                    _ => args[i..].iter().take_while(|a| a.parse::<f32>().is_ok()).count(),
+                };
+                let end = (i + count).min(args.len().saturating_sub(1)).max(i);
+                map.unknown_options.push(args[i-1..end].join(" "));
+                i = end;
+            },
+        }
+    }
+    if i >= args.len() {
+        return Err(line.wrong_arguments(statement,&format!("a file for `{}`",statement)));
+    }
+    let mut file = String::from(args[i]);
+    for arg in &args[i+1..] {
+        file += " ";
+        file += arg;
+    }
+    map.file = file;
+    Ok(map)
+}
+
+fn write_color<W : io::Write>(output : &mut W, statement : &str, color : Option<(f32,f32,f32)>) -> io::Result<()> {
+    if let Some((r,g,b)) = color {
+        let line : String = format!("{} {} {} {}\n",statement,r,g,b);
+        output.write_all(line.as_bytes())?;
+    }
+    Ok(())
+}
+
+impl TextureMap {
+    pub fn new(statement : String, file : String) -> TextureMap {
+        TextureMap {
+            statement,
+            file,
+            offset : None,
+            scale : None,
+            bump_multiplier : None,
+            clamp : None,
+            unknown_options : Vec::new(),
+        }
+    }
+}
+
+impl Material {
+    pub fn new(n : String) -> Material {
+        Material {
+            name : n,
+            ambient : None,
+            diffuse : None,
+            specular : None,
+            emissive : None,
+            specular_exponent : None,
+            optical_density : None,
+            dissolve : None,
+            illumination : None,
+            maps : Vec::new(),
+            unknown_statements : Vec::new(),
+        }
+    }
+}
+
+impl Default for MtlData {
+    fn default() -> MtlData {
+        MtlData::new()
+    }
+}
+
+impl MtlData {
+    /// Constructs a new empty `MtlData`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::MtlData;
+    ///
+    /// let data = MtlData::new();
+    /// ```
+    pub fn new() -> MtlData {
+        MtlData {
+            materials : Vec::new(),
+        }
+    }
+
+    /// Load a `MtlData` from any `BufRead`, like a `BufReader` or a `Cursor`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::MtlData;
+    ///
+    /// let mtl_str = "newmtl Material\nKd 0.8 0.8 0.8\nmap_Kd -s 2 2 diffuse.png\n";
+    /// let mut input = BufReader::new(mtl_str.as_bytes());
+    /// let data = MtlData::load(&mut input).ok().unwrap();
+    /// ```
+    pub fn load<R : io::BufRead>(input : &mut R) -> Result<MtlData,LoadingError> {
+        let mut data = MtlData::new();
+        let mut buf = String::new();
+        let mut nb : usize = 0;
+        while input.read_line(&mut buf)? > 0 {
+            nb += 1;
+            let line = SourceLine::new(nb,&buf);
+            // Skip comment
+            if !buf.starts_with('#') {
+                let mut iter = buf.split_whitespace();
+                let identifier = iter.next();
+                let args : Vec<_> = iter.collect();
+                if let Some(identifier) = identifier {
+                    if identifier == "newmtl" {
+                        if args.is_empty() {
+                            return Err(line.wrong_arguments(identifier,"a material name for `newmtl`"));
+                        }
+                        data.materials.push(Material::new(args.join(" ")));
+                    } else {
+                        // Every other statement needs a material
+                        let material = match data.materials.last_mut() {
+                            Some(m) => m,
+                            None => return Err(line.invalid(identifier,"`newmtl` before the statement")),
+                        };
+                        match identifier {
+                            "Ka" => material.ambient = Some(parse_color(identifier,&args,&line)?),
+                            "Kd" => material.diffuse = Some(parse_color(identifier,&args,&line)?),
+                            "Ks" => material.specular = Some(parse_color(identifier,&args,&line)?),
+                            "Ke" => material.emissive = Some(parse_color(identifier,&args,&line)?),
+                            "Ns" => material.specular_exponent = Some(parse_scalar::<f32>(identifier,&args,&line,"a float")?),
+                            "Ni" => material.optical_density = Some(parse_scalar::<f32>(identifier,&args,&line,"a float")?),
+                            "d" => material.dissolve = Some(parse_scalar::<f32>(identifier,&args,&line,"a float")?),
+                            "Tr" => material.dissolve = Some(1. - parse_scalar::<f32>(identifier,&args,&line,"a float")?),
+                            "illum" => material.illumination = Some(parse_scalar::<u32>(identifier,&args,&line,"an integer")?),
+                            "bump" | "disp" | "decal" | "refl" => {
+                                material.maps.push(parse_map(identifier,&args,&line)?);
+                            },
+                            _ if identifier.starts_with("map_") => {
+                                material.maps.push(parse_map(identifier,&args,&line)?);
+                            },
+                            _ => material.unknown_statements.push(join_args(&buf.split_whitespace().collect::<Vec<_>>())),
+                        }
+                    }
+                }
+            }
+            buf.clear();
+        }
+        Ok(data)
+    }
+
+    /// Load a `MtlData` from the file at `path`.
+    pub fn from_path<P : AsRef<Path>>(path : P) -> Result<MtlData,LoadingError> {
+        let mut input = io::BufReader::new(open(path.as_ref())?);
+        MtlData::load(&mut input)
+    }
+
+    /// Write in material library format in file.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufWriter;
+    /// use lwobj::{MtlData,Material};
+    ///
+    /// let mut data = MtlData::new();
+    /// data.materials.push(Material::new(String::from("Material")));
+    /// let mut output = BufWriter::new(Vec::<u8>::new());
+    /// assert!(data.write(&mut output).is_ok());
+    /// ```
+    pub fn write<W : io::Write>(&self, output : &mut W) -> Result<(),LoadingError> {
+        for m in &self.materials {
+            let line : String = format!("newmtl {}\n",m.name);
+            output.write_all(line.as_bytes())?;
+            write_color(output,"Ka",m.ambient)?;
+            write_color(output,"Kd",m.diffuse)?;
+            write_color(output,"Ks",m.specular)?;
+            write_color(output,"Ke",m.emissive)?;
+            if let Some(ns) = m.specular_exponent {
+                output.write_all(format!("Ns {}\n",ns).as_bytes())?;
+            }
+            if let Some(ni) = m.optical_density {
+                output.write_all(format!("Ni {}\n",ni).as_bytes())?;
+            }
+            if let Some(d) = m.dissolve {
+                output.write_all(format!("d {}\n",d).as_bytes())?;
+            }
+            if let Some(illum) = m.illumination {
+                output.write_all(format!("illum {}\n",illum).as_bytes())?;
+            }
+            for map in &m.maps {
+                output.write_all(map.statement.as_bytes())?;
+                if let Some((u,v,w)) = map.offset {
+                    output.write_all(format!(" -o {} {} {}",u,v,w).as_bytes())?;
+                }
+                if let Some((u,v,w)) = map.scale {
+                    output.write_all(format!(" -s {} {} {}",u,v,w).as_bytes())?;
+                }
+                if let Some(bm) = map.bump_multiplier {
+                    output.write_all(format!(" -bm {}",bm).as_bytes())?;
+                }
+                if let Some(clamp) = map.clamp {
+                    output.write_all(if clamp {" -clamp on"} else {" -clamp off"}.as_bytes())?;
+                }
+                for option in &map.unknown_options {
+                    output.write_all(format!(" {}",option).as_bytes())?;
+                }
+                output.write_all(format!(" {}\n",map.file).as_bytes())?;
+            }
+            for statement in &m.unknown_statements {
+                output.write_all(format!("{}\n",statement).as_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use std::io::BufWriter;
+    use std::str;
+    use obj::LoadingError;
+    use mtl::*;
+
+    #[test]
+    fn load_material() {
+        let mut expected = Material::new(String::from("Material"));
+        expected.ambient = Some((1.,1.,1.));
+        expected.diffuse = Some((0.8,0.5,0.2));
+        expected.specular = Some((0.5,0.5,0.5));
+        expected.emissive = Some((0.,0.,0.));
+        expected.specular_exponent = Some(96.5);
+        expected.optical_density = Some(1.);
+        expected.dissolve = Some(0.75);
+        expected.illumination = Some(2);
+        let mtl_str =
+        r#"# Blender MTL File: 'None'
+newmtl Material
+        Ns 96.5
+        Ka 1.000000 1.000000 1.000000
+        Kd 0.8 0.5 0.2
+        Ks 0.5
+        Ke 0.000000 0.000000 0.000000
+        Ni 1.000000
+        Tr 0.25
+        illum 2"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        let data = MtlData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![expected],data.materials);
+    }
+
+    #[test]
+    fn load_maps() {
+        let mut map1 = TextureMap::new(String::from("map_Kd"),String::from("textures/diffuse map.png"));
+        map1.offset = Some((0.5,0.,0.));
+        map1.scale = Some((2.,2.,1.));
+        map1.clamp = Some(true);
+        let mut map2 = TextureMap::new(String::from("map_bump"),String::from("normal.png"));
+        map2.bump_multiplier = Some(0.5);
+        let map3 = TextureMap::new(String::from("disp"),String::from("height.png"));
+        // Missing components are 0 for an offset and 1 for a scale
+        let mut map4 = TextureMap::new(String::from("map_Ks"),String::from("specular.png"));
+        map4.offset = Some((0.25,0.5,0.));
+        map4.scale = Some((2.,1.,1.));
+        let mtl_str =
+        r#"newmtl Material
+        map_Kd -o 0.5 -s 2 2 1 -clamp on textures/diffuse map.png
+        map_bump -bm 0.5 normal.png
+        disp height.png
+        map_Ks -o 0.25 0.5 -s 2 specular.png"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        let data = MtlData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![map1,map2,map3,map4],data.materials[0].maps);
+    }
+
+    #[test]
+    fn load_invalid_line() {
+        let mtl_str =
+        r#"Kd 1 1 1
+        newmtl Material"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        match MtlData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(context) => assert!(context.line == 1),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_write_unknown() {
+        let mut map1 = TextureMap::new(String::from("refl"),String::from("env.png"));
+        map1.unknown_options = vec![String::from("-type sphere"),String::from("-blendu off")];
+        let mut map2 = TextureMap::new(String::from("map_Kd"),String::from("diffuse map.png"));
+        map2.scale = Some((2.,2.,1.));
+        map2.unknown_options = vec![String::from("-mm 0 1"),String::from("-x 1")];
+        let mtl_str =
+        r#"newmtl Material
+refl -type sphere -blendu off env.png
+map_Kd -mm 0 1 -s 2 2 -x 1 diffuse map.png
+Tf 1 1 1
+sharpness 60
+"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        let data = MtlData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![map1,map2],data.materials[0].maps);
+        assert_eq!(vec![String::from("Tf 1 1 1"),String::from("sharpness 60")],data.materials[0].unknown_statements);
+
+        let expected =
+        r#"newmtl Material
+refl -type sphere -blendu off env.png
+map_Kd -s 2 2 1 -mm 0 1 -x 1 diffuse map.png
+Tf 1 1 1
+sharpness 60
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn load_wrong_number_of_arguments() {
+        let mtl_str =
+        r#"newmtl Material
+        Kd 1 1
+        Ns 10"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        match MtlData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+
+        let mtl_str =
+        r#"newmtl Material
+        map_Kd -bm 2"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        match MtlData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_parse_err() {
+        let mtl_str =
+        r#"newmtl Material
+        Kd 1 1 1
+        illum 2.5"#;
+
+        let mut input = BufReader::new(mtl_str.as_bytes());
+        match MtlData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(context) => assert!(context.line == 3),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn write_materials() {
+        let mut mat1 = Material::new(String::from("Red"));
+        mat1.diffuse = Some((1.,0.,0.));
+        mat1.specular_exponent = Some(10.);
+        mat1.dissolve = Some(0.5);
+        mat1.illumination = Some(2);
+        let mut map = TextureMap::new(String::from("map_Kd"),String::from("red.png"));
+        map.scale = Some((2.,2.,1.));
+        map.clamp = Some(false);
+        mat1.maps.push(map);
+        let mut mat2 = Material::new(String::from("Blue"));
+        mat2.ambient = Some((0.,0.,1.));
+        let mut data = MtlData::new();
+        data.materials = vec![mat1,mat2];
+        let expected =
+        r#"newmtl Red
+Kd 1 0 0
+Ns 10
+d 0.5
+illum 2
+map_Kd -s 2 2 1 -clamp off red.png
+newmtl Blue
+Ka 0 0 1
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+}
@@ -0,0 +1,279 @@
+// Positions are converted to f64 and normals back to `Float`, which is f64 with the `f64` feature.
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+
+use std::collections::HashMap;
+use obj::{Float,ObjData};
+use face::NormalIndex;
+use triangulate::newell;
+
+/// How `ObjData::compute_normals` computes the normals of vertices.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum NormalMode {
+    /// Normal of the face at each of its vertices.
+    Flat,
+    /// Sum of the normals of the faces around a vertex weighted by their area.
+    /// Faces making an angle larger than the crease angle in degrees don't share their normals.
+    Area(f32),
+    /// Sum of the normals of the faces around a vertex weighted by their angle at the vertex.
+    /// Faces making an angle larger than the crease angle in degrees don't share their normals.
+    Angle(f32),
+}
+
+fn sub(a : [f64;3], b : [f64;3]) -> [f64;3] {
+    [a[0]-b[0],a[1]-b[1],a[2]-b[2]]
+}
+
+fn dot(a : [f64;3], b : [f64;3]) -> f64 {
+    a[0]*b[0]+a[1]*b[1]+a[2]*b[2]
+}
+
+// `a` with a length of 1, or the null vector if `a` is null.
+fn normalize(a : [f64;3]) -> [f64;3] {
+    let length = dot(a,a).sqrt();
+    if length > 0. {
+        [a[0]/length,a[1]/length,a[2]/length]
+    } else {
+        [0.;3]
+    }
+}
+
+// Angle in radians between the edges of the corner `k` of a polygon.
+fn corner_angle(points : &[[f64;3]], k : usize) -> f64 {
+    let n = points.len();
+    let a = normalize(sub(points[(k+n-1) % n],points[k]));
+    let b = normalize(sub(points[(k+1) % n],points[k]));
+    dot(a,b).clamp(-1.,1.).acos()
+}
+
+impl ObjData {
+    /// Compute the normals of the vertices of faces which have none.
+    ///
+    /// The normals are added to `normals`, each distinct normal once, and referenced by
+    /// the vertices of faces. Vertices which already have a normal are unchanged.
+    ///
+    /// When the faces have smoothing groups, only faces of the same smoothing group share
+    /// their normals and faces without smoothing group have flat normals.
+    /// Vertices of faces without area take the normal of the faces around their position,
+    /// and are left without normal if these faces have no area either.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{ObjData,NormalMode,NormalIndex};
+    ///
+    /// // Two faces of a cube
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\n\
+    ///                f 1 4 3 2\nf 1 2 6 5\n";
+    /// let mut data = ObjData::from_str(obj_str).ok().unwrap();
+    /// data.compute_normals(NormalMode::Flat);
+    /// assert_eq!(vec![(0.,0.,-1.),(0.,-1.,0.)],data.normals);
+    /// assert_eq!(Some(NormalIndex(1)),data.faces.get(1).unwrap().vertices[0].normal);
+    ///
+    /// // Both faces share the normal of their common edge with a crease angle larger than 90 degrees
+    /// let mut data = ObjData::from_str(obj_str).ok().unwrap();
+    /// data.compute_normals(NormalMode::Area(100.));
+    /// assert_eq!(3,data.normals.len());
+    /// assert_eq!(data.faces.get(0).unwrap().vertices[0].normal,data.faces.get(1).unwrap().vertices[0].normal);
+    /// ```
+    pub fn compute_normals(&mut self, mode : NormalMode) {
+        let offsets = self.faces.offsets();
+        let points : Vec<[f64;3]> = self.faces.vertices().iter().map(|vertex| {
+            let (x,y,z,_) = self[vertex.position];
+            [x as f64,y as f64,z as f64]
+        }).collect();
+        let face_points = |f : usize| &points[offsets[f]..offsets[f+1]];
+        // Normal of every face, whose length is twice its area
+        let areas : Vec<[f64;3]> = (0..self.faces.len()).map(|f| newell(face_points(f))).collect();
+        let units : Vec<[f64;3]> = areas.iter().map(|&n| normalize(n)).collect();
+        let group = |f : usize| self.smoothing_groups.get(f).cloned().unwrap_or(0);
+        let smoothing = self.smoothing_groups.iter().any(|&s| s != 0);
+
+        // Corners `(f,k)` around every position are `corners[first[v]..first[v+1]]`
+        let mut first = vec![0; self.vertices.len()+1];
+        for vertex in self.faces.vertices() {
+            first[vertex.position.0+1] += 1;
+        }
+        for v in 0..self.vertices.len() {
+            first[v+1] += first[v];
+        }
+        let mut next = first.clone();
+        let mut corners = vec![(0,0); first[self.vertices.len()]];
+        for f in 0..self.faces.len() {
+            for (k,vertex) in self.faces[f].iter().enumerate() {
+                let v = vertex.position.0;
+                corners[next[v]] = (f,k);
+                next[v] += 1;
+            }
+        }
+
+        let smooth = |f : usize, v : usize, crease : f32, angle : bool| -> [f64;3] {
+            if smoothing && group(f) == 0 {
+                return units[f];
+            }
+            let cos = (crease as f64).to_radians().cos();
+            let mut normal = [0.;3];
+            for &(g,k) in &corners[first[v]..first[v+1]] {
+                if (smoothing && group(g) != group(f)) || (crease < 180. && dot(units[f],units[g]) < cos) {
+                    continue;
+                }
+                let (n,weight) = if angle {
+                    (units[g],corner_angle(face_points(g),k))
+                } else {
+                    (areas[g],1.)
+                };
+                for i in 0..3 {
+                    normal[i] += n[i] * weight;
+                }
+            }
+            match normalize(normal) {
+                [0.,0.,0.] => units[f],
+                normal => normal,
+            }
+        };
+
+        // Normal of the faces around the position `v`
+        let around = |v : usize| -> [f64;3] {
+            let mut normal = [0.;3];
+            for &(g,_) in &corners[first[v]..first[v+1]] {
+                for i in 0..3 {
+                    normal[i] += areas[g][i];
+                }
+            }
+            normalize(normal)
+        };
+
+        let mut normals : Vec<Option<[f64;3]>> = vec![None; points.len()];
+        for f in 0..self.faces.len() {
+            for (k,vertex) in self.faces[f].iter().enumerate() {
+                if vertex.normal.is_some() {
+                    continue;
+                }
+                let v = vertex.position.0;
+                let normal = match mode {
+                    NormalMode::Flat => units[f],
+                    NormalMode::Area(crease) => smooth(f,v,crease,false),
+                    NormalMode::Angle(crease) => smooth(f,v,crease,true),
+                };
+                normals[offsets[f]+k] = match normal {
+                    [0.,0.,0.] => match around(v) {
+                        [0.,0.,0.] => None,
+                        normal => Some(normal),
+                    },
+                    normal => Some(normal),
+                };
+            }
+        }
+
+        // Normals are added once, being compared as written in `normals`
+        let mut indices : HashMap<[u64;3],NormalIndex> = HashMap::new();
+        for (vertex,normal) in self.faces.vertices_mut().iter_mut().zip(normals) {
+            if let Some(n) = normal {
+                let n = (n[0] as Float,n[1] as Float,n[2] as Float);
+                let key = [n.0.to_bits() as u64,n.1.to_bits() as u64,n.2.to_bits() as u64];
+                let normals = &mut self.normals;
+                vertex.normal = Some(*indices.entry(key).or_insert_with(|| {
+                    normals.push(n);
+                    NormalIndex(normals.len()-1)
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use face::NormalIndex;
+    use normals::*;
+
+    fn close(a : (Float,Float,Float), b : (Float,Float,Float)) -> bool {
+        (a.0-b.0).abs() < 1e-4 && (a.1-b.1).abs() < 1e-4 && (a.2-b.2).abs() < 1e-4
+    }
+
+    // Normal of the vertex `k` of the face `f`.
+    fn normal(data : &ObjData, f : usize, k : usize) -> (Float,Float,Float) {
+        data[data.faces.get(f).unwrap().vertices[k].normal.unwrap()]
+    }
+
+    // Cube of side 1 with a face split in two triangles.
+    const CUBE : &str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\n\
+                         f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8\nf 3 8 7\nf 4 1 5 8\n";
+
+    #[test]
+    fn flat_normals() {
+        let mut data = ObjData::from_str(CUBE).ok().unwrap();
+        data.compute_normals(NormalMode::Flat);
+        assert_eq!(6,data.normals.len());
+        assert_eq!((0.,1.,0.),normal(&data,4,0));
+        assert_eq!((0.,1.,0.),normal(&data,5,2));
+        assert_eq!((-1.,0.,0.),normal(&data,6,3));
+    }
+
+    #[test]
+    fn smooth_normals() {
+        let corner = 1. / (3. as Float).sqrt();
+        // Each vertex of the cube has the same normal with a crease angle larger than 90 degrees
+        let mut data = ObjData::from_str(CUBE).ok().unwrap();
+        data.compute_normals(NormalMode::Angle(120.));
+        assert_eq!(8,data.normals.len());
+        assert!(close((corner,corner,corner),normal(&data,1,2)));
+        assert!(close((-corner,corner,-corner),normal(&data,0,1)));
+
+        // Area weighted normals depend on the triangulation of the back face
+        let mut data = ObjData::from_str(CUBE).ok().unwrap();
+        data.compute_normals(NormalMode::Area(120.));
+        let n = normal(&data,1,2);
+        assert!(close((2./3.,1./3.,2./3.),n));
+        assert!(close((-2./3.,1./3.,-2./3.),normal(&data,0,1)));
+
+        // Faces are flat with a crease angle smaller than 90 degrees
+        let mut data = ObjData::from_str(CUBE).ok().unwrap();
+        data.compute_normals(NormalMode::Area(60.));
+        assert_eq!(6,data.normals.len());
+        assert_eq!((0.,0.,1.),normal(&data,1,2));
+    }
+
+    #[test]
+    fn smoothing_group_normals() {
+        // Front, back and left faces are smoothed together, other faces are flat
+        let obj_str = CUBE.replace("f 1 4 3 2\n","s 1\nf 1 4 3 2\ns off\n")
+                          .replace("f 1 2 6 5\n","s 2\nf 1 2 6 5\ns off\n")
+                          .replace("f 3 4 8\n","s 2\nf 3 4 8\n");
+        let mut data = ObjData::from_str(&obj_str).ok().unwrap();
+        data.compute_normals(NormalMode::Angle(180.));
+        let side = 1. / (2. as Float).sqrt();
+        assert_eq!((0.,0.,-1.),normal(&data,0,1));
+        assert_eq!((0.,0.,1.),normal(&data,1,0));
+        assert_eq!((1.,0.,0.),normal(&data,3,0));
+        assert!(close((-side,-side,0.),normal(&data,2,0)));
+        assert!(close((-side,side,0.),normal(&data,5,1)));
+        assert!(close((0.,1.,0.),normal(&data,5,0)));
+    }
+
+    #[test]
+    fn keep_normals() {
+        let mut data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 0\nvn 1 0 0\nf 1//1 2 3\nf 1 2 3 4\n").ok().unwrap();
+        data.compute_normals(NormalMode::Flat);
+        assert_eq!(vec![(1.,0.,0.),(0.,0.,1.)],data.normals);
+        let face = data.faces.get(0).unwrap();
+        assert_eq!(vec![Some(NormalIndex(0)),Some(NormalIndex(1)),Some(NormalIndex(1))],face.normals().collect::<Vec<_>>());
+        let face = data.faces.get(1).unwrap();
+        assert_eq!(Some(NormalIndex(1)),face.vertices[3].normal);
+    }
+
+    #[test]
+    fn degenerate_face_normals() {
+        // The flat face takes the normal of its neighbour where they share a position,
+        // other vertices of faces without area get no normal
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 2 0 0\nv 5 5 5\nf 1 2 3\nf 1 2 4\nf 5 5 5\n";
+        for &mode in &[NormalMode::Flat,NormalMode::Area(60.),NormalMode::Angle(180.)] {
+            let mut data = ObjData::from_str(obj_str).ok().unwrap();
+            data.compute_normals(mode);
+            assert_eq!(vec![(0.,0.,1.)],data.normals);
+            assert_eq!((0.,0.,1.),normal(&data,1,0));
+            assert_eq!((0.,0.,1.),normal(&data,1,1));
+            assert_eq!(None,data.faces.get(1).unwrap().vertices[2].normal);
+            assert_eq!(None,data.faces.get(2).unwrap().vertices[0].normal);
+        }
+    }
+}
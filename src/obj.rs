@@ -1,50 +1,198 @@
-use std::io::BufRead;
-use std::io::Write;
 use std::io;
+use std::fs::File;
+use std::path::Path;
 use std::str::FromStr;
-use std::collections::HashSet;
+use std::error;
+use std::fmt;
+use std::ops::Index;
+use std::borrow::Cow;
+use mtl::MtlData;
+use face::{Face,Faces,FaceVertex,VertexIndex,TexCoordIndex,NormalIndex};
+use freeform::{Curve,Curve2,Surface,FreeFormWriter};
+use visitor::{ObjVisitor,visit};
+use bytes::visit_bytes;
+use parallel::visit_bytes_parallel;
+
+/// Scalar type of vertex data, `f64` with the `f64` feature and `f32` otherwise.
+#[cfg(not(feature = "f64"))]
+pub type Float = f32;
+/// Scalar type of vertex data, `f64` with the `f64` feature and `f32` otherwise.
+#[cfg(feature = "f64")]
+pub type Float = f64;
+
+/// Location and description of a loading error.
+#[derive(PartialEq, Debug, Clone)]
+pub struct ErrorContext {
+    /// Line of the error, starting at 1.
+    pub line : usize,
+    /// Column of `token` in the line, starting at 1.
+    pub column : usize,
+    /// Offending token.
+    pub token : String,
+    /// Description of what was expected.
+    pub expected : String,
+    /// Text of the line without its end of line, empty if it isn't known.
+    pub text : String,
+}
 
 #[derive(Debug)]
 pub enum LoadingError {
-    InvalidLine(usize),
-    WrongNumberOfArguments(usize),
-    Parse(usize),
+    /// A statement is unknown or not allowed here.
+    InvalidLine(ErrorContext),
+    WrongNumberOfArguments(ErrorContext),
+    /// A value can't be parsed.
+    Parse(ErrorContext),
+    /// A face references an element which doesn't exist.
+    /// `index` is the index as written in the file.
+    IndexOutOfRange { context : ErrorContext, kind : IndexKind, index : isize },
     Io(io::Error),
 }
 
+/// Kind of element referenced by an index.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum IndexKind {
+    Vertex,
+    TexCoord,
+    Normal,
+    ParameterVertex,
+    Curve,
+}
+
+/// Group given by `g`, with the elements it contains as sorted lists of indices.
 #[derive(PartialEq, Debug)]
 pub struct Group {
     pub name : String,
-    pub indexes : HashSet<usize>,
+    /// Faces of the group.
+    pub indexes : Vec<usize>,
+    /// Lines of the group.
+    pub lines : Vec<usize>,
+    /// Point elements of the group.
+    pub points : Vec<usize>,
+    /// Free-form curves of the group.
+    pub curves : Vec<usize>,
+    /// Free-form curves in parameter space of the group.
+    pub curves2 : Vec<usize>,
+    /// Free-form surfaces of the group.
+    pub surfaces : Vec<usize>,
 }
 
 #[derive(PartialEq, PartialOrd,Debug)]
 pub struct Object {
     pub name : String,
-    pub primitives : Vec<usize>
+    pub primitives : Vec<usize>,
+    pub lines : Vec<usize>,
+    pub points : Vec<usize>,
+    pub curves : Vec<usize>,
+    pub curves2 : Vec<usize>,
+    pub surfaces : Vec<usize>,
+    /// Elements of the object in the order of the file, which is the order in which they
+    /// are written. If it is empty, they are written by kind.
+    pub elements : Vec<Element>,
+}
+
+/// Element of an object, given by its index in the list of its kind.
+#[derive(PartialEq, PartialOrd, Debug, Clone, Copy)]
+pub enum Element {
+    /// Face of `faces`.
+    Face(usize),
+    /// Line of `lines`.
+    Line(usize),
+    /// Point element of `points`.
+    Point(usize),
+    /// Free-form curve of `curves`.
+    Curve(usize),
+    /// Free-form curve in parameter space of `curves2`.
+    Curve2(usize),
+    /// Free-form surface of `surfaces`.
+    Surface(usize),
+    /// Unsupported statement of `unknown_statements`.
+    Unknown(usize),
+}
+
+/// Options changing how an `ObjData` is loaded.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct LoadOptions {
+    /// Keep unsupported statements in `unknown_statements` and report them in `warnings`
+    /// instead of failing with `LoadingError::InvalidLine`.
+    pub lenient : bool,
+}
+
+/// A statement kept verbatim by a lenient loading.
+#[derive(PartialEq, Debug, Clone)]
+pub struct UnknownStatement {
+    /// Line of the statement, starting at 1.
+    pub line : usize,
+    /// Text of the statement without its end of line.
+    pub text : String,
+    /// Index of the object active at this statement.
+    pub object : Option<usize>,
+    /// Indices of the groups active at this statement.
+    pub groups : Vec<usize>,
+}
+
+/// Options changing how an `ObjData` is written.
+#[derive(PartialEq, Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Write face indices relative to the last elements (e.g. `f -3 -2 -1`)
+    /// so that written objects can be concatenated textually.
+    pub relative_indices : bool,
 }
 
 /// A struct containing all data store by wavefront.
 pub struct ObjData {
     /// List of vertices `(x,y,z,w)`.
     /// Its coordinates are (x,y,z) and w is the weight for rational curves and surfaces.
-    pub vertices : Vec<(f32,f32,f32,f32)>,
+    pub vertices : Vec<(Float,Float,Float,Float)>,
     /// List of normal vector with componetns `(x,y,z)`.
-    pub normals : Vec<(f32,f32,f32)>,
+    pub normals : Vec<(Float,Float,Float)>,
     /// List of texture coordinates `(u,v,w)`.
     /// u and v are the value for the horizontal and vertical direction.
     /// w is the value for the depth of the texture.
-    pub texcoords : Vec<(f32,f32,f32)>,
+    pub texcoords : Vec<(Float,Float,Float)>,
     /// List of faces.
-    /// Each Face is a list of `(v,vt,vn)`.
+    /// Each vertex of a face references its position, and its texture coordinates
+    /// and normal if it has some.
+    pub faces : Faces,
+    /// List of lines.
+    /// Each line is a list of `(v,vt)`.
     /// v is the index of vertex.
     /// vt is the index of its texture coordinate if it has one.
-    /// vn is the index of its normal vector if it has one.
-    pub faces : Vec<Vec<(usize,Option<usize>,Option<usize>)>>,
+    pub lines : Vec<Vec<(usize,Option<usize>)>>,
+    /// List of points.
+    /// Each point element is a list of vertex indices.
+    pub points : Vec<Vec<usize>>,
+    /// List of parameter space vertices `(u,v,w)` given by `vp`.
+    /// v is 0 for points of curves and w is the weight for rational curves.
+    pub parameter_vertices : Vec<(Float,Float,Float)>,
+    /// List of free-form curves given by `curv`.
+    pub curves : Vec<Curve>,
+    /// List of free-form curves in parameter space given by `curv2`.
+    pub curves2 : Vec<Curve2>,
+    /// List of free-form surfaces given by `surf`.
+    pub surfaces : Vec<Surface>,
     /// List of Objects
     pub objects : Vec<Object>,
-    /// List of groups
-    pub groups : Vec<Group>
+    /// List of groups.
+    /// Elements of the group `default` are in no group.
+    pub groups : Vec<Group>,
+    /// List of material library files referenced by `mtllib`.
+    pub material_libraries : Vec<String>,
+    /// List of material names referenced by `usemtl`.
+    pub materials : Vec<String>,
+    /// Material of each face.
+    /// It is the index in `materials` of the material active when the face was declared.
+    pub face_materials : Vec<Option<usize>>,
+    /// Smoothing group of each face given by `s`.
+    /// 0 means that smoothing is off.
+    pub smoothing_groups : Vec<u32>,
+    /// Material of each free-form surface, like `face_materials`.
+    pub surface_materials : Vec<Option<usize>>,
+    /// Smoothing group of each free-form surface, like `smoothing_groups`.
+    pub surface_smoothing_groups : Vec<u32>,
+    /// List of unsupported statements kept by a lenient loading.
+    pub unknown_statements : Vec<UnknownStatement>,
+    /// Non-fatal errors found by a lenient loading.
+    pub warnings : Vec<LoadingError>,
 }
 
 impl From<io::Error> for LoadingError {
@@ -53,23 +201,435 @@ impl From<io::Error> for LoadingError {
     }
 }
 
-fn parse<T : FromStr>(it : Vec<&str>, nb : usize) -> Result<Vec<T>, LoadingError> {
+impl LoadingError {
+    /// Context of the error, `None` for an I/O error.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match *self {
+            LoadingError::InvalidLine(ref context) |
+            LoadingError::WrongNumberOfArguments(ref context) |
+            LoadingError::Parse(ref context) |
+            LoadingError::IndexOutOfRange { ref context, .. } => Some(context),
+            LoadingError::Io(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for IndexKind {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            IndexKind::Vertex => "vertex",
+            IndexKind::TexCoord => "texture coordinate",
+            IndexKind::Normal => "normal",
+            IndexKind::ParameterVertex => "parameter vertex",
+            IndexKind::Curve => "curve",
+        })
+    }
+}
+
+impl fmt::Display for LoadingError {
+    /// Write the error followed by the line underlined at the offending token.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 1 abc 2\n".as_bytes());
+    /// let err = ObjData::load(&mut input).err().unwrap();
+    /// let expected = "line 1, column 5: cannot parse `abc`, expected 3 or 4 floats for `v`\n \
+    ///                 1 | v 1 abc 2\n   |     ^^^";
+    /// assert_eq!(expected,err.to_string());
+    /// ```
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let context = match *self {
+            LoadingError::InvalidLine(ref context) => {
+                write!(f,"line {}, column {}: invalid statement",context.line,context.column)?;
+                context
+            },
+            LoadingError::WrongNumberOfArguments(ref context) => {
+                write!(f,"line {}, column {}: wrong number of arguments",context.line,context.column)?;
+                context
+            },
+            LoadingError::Parse(ref context) => {
+                write!(f,"line {}, column {}: cannot parse",context.line,context.column)?;
+                context
+            },
+            LoadingError::IndexOutOfRange { ref context, kind, .. } => {
+                write!(f,"line {}, column {}: {} index out of range",context.line,context.column,kind)?;
+                context
+            },
+            LoadingError::Io(ref err) => return write!(f,"I/O error: {}",err),
+        };
+        if !context.token.is_empty() {
+            write!(f," `{}`",context.token)?;
+        }
+        if !context.expected.is_empty() {
+            write!(f,", expected {}",context.expected)?;
+        }
+        if !context.text.is_empty() {
+            let number = context.line.to_string();
+            let margin = " ".repeat(number.len());
+            let underline = "^".repeat(context.token.chars().count().max(1));
+            write!(f,"\n {} | {}",number,context.text)?;
+            write!(f,"\n {} | {}{}",margin," ".repeat(context.column-1),underline)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for LoadingError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            LoadingError::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// A line being loaded, used to locate errors.
+pub struct SourceLine<'a> {
+    // Number of the line, starting at 1.
+    pub nb : usize,
+    pub text : &'a str,
+}
+
+impl<'a> SourceLine<'a> {
+    pub fn new(nb : usize, text : &'a str) -> SourceLine<'a> {
+        SourceLine {
+            nb,
+            text : text.trim_end_matches(['\n','\r']),
+        }
+    }
+
+    // Column of `token`, which is a slice of the line like every argument.
+    pub fn column(&self, token : &str) -> usize {
+        let start = self.text.as_ptr() as usize;
+        let offset = (token.as_ptr() as usize).wrapping_sub(start);
+        if offset <= self.text.len() && self.text.is_char_boundary(offset) {
+            self.text[..offset].chars().count() + 1
+        } else {
+            1
+        }
+    }
+
+    pub fn keyword(&self) -> &'a str {
+        self.text.split_whitespace().next().unwrap_or("")
+    }
+
+    // Argument `i` following the keyword, empty if there is none.
+    pub fn argument(&self, i : usize) -> &'a str {
+        self.text.split_whitespace().nth(i+1).unwrap_or("")
+    }
+
+    // Index `component` of the `v/vt/vn` argument `i`.
+    pub fn index_token(&self, i : usize, component : usize) -> &'a str {
+        self.argument(i).split('/').nth(component).unwrap_or("")
+    }
+
+    pub fn context(&self, token : &str, expected : &str) -> ErrorContext {
+        ErrorContext {
+            line : self.nb,
+            column : self.column(token),
+            token : String::from(token),
+            expected : String::from(expected),
+            text : String::from(self.text),
+        }
+    }
+
+    pub fn invalid(&self, token : &str, expected : &str) -> LoadingError {
+        LoadingError::InvalidLine(self.context(token,expected))
+    }
+
+    pub fn wrong_arguments(&self, token : &str, expected : &str) -> LoadingError {
+        LoadingError::WrongNumberOfArguments(self.context(token,expected))
+    }
+
+    pub fn parse_error(&self, token : &str, expected : &str) -> LoadingError {
+        LoadingError::Parse(self.context(token,expected))
+    }
+}
+
+pub fn parse<T : FromStr>(it : Vec<&str>, line : &SourceLine, expected : &str) -> Result<Vec<T>, LoadingError> {
     let mut vec : Vec<T> = Vec::new();
     for s in it {
         let val = match s.parse::<T>() {
             Ok(v) => v,
-            Err(_) => return Err(LoadingError::Parse(nb)),
+            Err(_) => return Err(line.parse_error(s,expected)),
         };
         vec.push(val);
     }
-    return Ok(vec);
+    Ok(vec)
+}
+
+// Open the file at `path`, naming it in the error.
+pub fn open(path : &Path) -> Result<File, LoadingError> {
+    File::open(path).map_err(|err| {
+        LoadingError::Io(io::Error::new(err.kind(),format!("{}: {}",path.display(),err)))
+    })
+}
+
+// Convert an index of a `f` statement into an index in a list of `len` elements.
+// Positive indices start at 1 and negative ones count backward from the last element.
+fn resolve_index(i : isize, len : usize) -> Option<usize> {
+    if i > 0 {
+        Some(i as usize - 1)
+    } else if i < 0 && i.unsigned_abs() <= len {
+        Some(len - i.unsigned_abs())
+    } else {
+        None
+    }
+}
+
+// Largest index of a kind referenced by elements, with the line referencing it,
+// and number of elements of this kind loaded so far.
+// Positive indices can only be checked once every element is loaded.
+pub struct MaxIndex {
+    kind : IndexKind,
+    index : Option<usize>,
+    line : usize,
+    column : usize,
+    pub len : usize,
+}
+
+// Largest index referenced for each kind of element.
+pub struct MaxIndexes {
+    pub v : MaxIndex,
+    pub vt : MaxIndex,
+    pub vn : MaxIndex,
+    pub vp : MaxIndex,
+    pub curv2 : MaxIndex,
+}
+
+impl MaxIndex {
+    fn new(kind : IndexKind) -> MaxIndex {
+        MaxIndex {
+            kind,
+            index : None,
+            line : 0,
+            column : 0,
+            len : 0,
+        }
+    }
+
+    // Resolve the index `i` written as the `component` of the argument `arg` of `line`
+    // and remember it if it's the largest one.
+    pub fn resolve(&mut self, i : isize, line : &SourceLine, arg : usize, component : usize) -> Result<usize, LoadingError> {
+        self.resolve_token(i,line,|| line.index_token(arg,component))
+    }
+
+    // Same as `resolve` with the index written as `token`, only called to locate it.
+    pub fn resolve_token<'t, F : Fn() -> &'t str>(&mut self, i : isize, line : &SourceLine, token : F) -> Result<usize, LoadingError> {
+        let index = match resolve_index(i,self.len) {
+            Some(index) => index,
+            None => {
+                let expected = if i == 0 {
+                    String::from("a positive or negative index")
+                } else {
+                    format!("a negative index not below -{}",self.len)
+                };
+                let mut context = line.context(token(),&expected);
+                if context.token.is_empty() {
+                    context.token = i.to_string();
+                }
+                return Err(LoadingError::IndexOutOfRange { context, kind : self.kind, index : i });
+            },
+        };
+        let largest = match self.index {
+            Some(i) => index > i,
+            None => true,
+        };
+        if largest {
+            self.index = Some(index);
+            self.line = line.nb;
+            self.column = line.column(token());
+        }
+        Ok(index)
+    }
+
+    fn check(&self) -> Result<(), LoadingError> {
+        let len = self.len;
+        match self.index {
+            Some(i) if i >= len => Err(LoadingError::IndexOutOfRange {
+                context : ErrorContext {
+                    line : self.line,
+                    column : self.column,
+                    token : (i+1).to_string(),
+                    expected : format!("an index not above {}",len),
+                    text : String::new(),
+                },
+                kind : self.kind,
+                index : i as isize + 1,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl MaxIndexes {
+    pub fn new() -> MaxIndexes {
+        MaxIndexes {
+            v : MaxIndex::new(IndexKind::Vertex),
+            vt : MaxIndex::new(IndexKind::TexCoord),
+            vn : MaxIndex::new(IndexKind::Normal),
+            vp : MaxIndex::new(IndexKind::ParameterVertex),
+            curv2 : MaxIndex::new(IndexKind::Curve),
+        }
+    }
+
+    // Resolve a `v/vt/vn` reference written as the argument `arg` of a `f` or `surf` statement.
+    pub fn resolve_vertex_ref(&mut self, vertex : (isize,Option<isize>,Option<isize>), line : &SourceLine, arg : usize)
+        -> Result<(usize,Option<usize>,Option<usize>), LoadingError> {
+        self.resolve_vertex_token(vertex,line,|| line.argument(arg))
+    }
+
+    // Same as `resolve_vertex_ref` with the reference written as `token`, only called to locate it.
+    pub fn resolve_vertex_token<'t, F : Fn() -> &'t str>(&mut self, (v,vt,vn) : (isize,Option<isize>,Option<isize>),
+                                                         line : &SourceLine, token : F)
+        -> Result<(usize,Option<usize>,Option<usize>), LoadingError> {
+        let token = &token;
+        let component = |c| move || token().split('/').nth(c).unwrap_or("");
+        let v = self.v.resolve_token(v,line,component(0))?;
+        let vt = match vt {
+            Some(i) => Some(self.vt.resolve_token(i,line,component(1))?),
+            None => None,
+        };
+        let vn = match vn {
+            Some(i) => Some(self.vn.resolve_token(i,line,component(2))?),
+            None => None,
+        };
+        Ok((v,vt,vn))
+    }
+
+    pub fn check(&self) -> Result<(), LoadingError> {
+        self.v.check()?;
+        self.vt.check()?;
+        self.vn.check()?;
+        self.vp.check()?;
+        self.curv2.check()
+    }
+}
+
+// Groups containing each element of a kind, as sorted lists of group indices
+// stored one after the other.
+struct Membership {
+    offsets : Vec<usize>,
+    groups : Vec<usize>,
+}
+
+impl Membership {
+    // Build the groups of `len` elements from the elements of each group.
+    fn new<'a, I : Iterator<Item=&'a [usize]> + Clone>(len : usize, elements : I) -> Membership {
+        let mut offsets = vec![0; len+1];
+        for &i in elements.clone().flatten() {
+            if i < len {
+                offsets[i+1] += 1;
+            }
+        }
+        for i in 0..len {
+            offsets[i+1] += offsets[i];
+        }
+        let mut next = offsets.clone();
+        let mut groups = vec![0; offsets[len]];
+        for (j,elements) in elements.enumerate() {
+            for &i in elements.iter().filter(|&&i| i < len) {
+                groups[next[i]] = j;
+                next[i] += 1;
+            }
+        }
+        Membership {
+            offsets,
+            groups,
+        }
+    }
+
+    fn groups(&self, i : usize) -> &[usize] {
+        match (self.offsets.get(i),self.offsets.get(i+1)) {
+            (Some(&start),Some(&end)) => &self.groups[start..end],
+            _ => &[],
+        }
+    }
+}
+
+// Active statements and groups of each element while writing an `ObjData`.
+struct WriteState {
+    relative : bool,
+    groups : Vec<usize>,
+    material : Option<usize>,
+    smoothing : u32,
+    freeform : FreeFormWriter,
+    face_groups : Membership,
+    line_groups : Membership,
+    point_groups : Membership,
+    curve_groups : Membership,
+    curve2_groups : Membership,
+    surface_groups : Membership,
+}
+
+// Write a `s` statement if `smoothing` differs from the active smoothing group.
+fn write_smoothing_group<W : io::Write>(output : &mut W, state : &mut WriteState, smoothing : u32) -> Result<(),LoadingError> {
+    if state.smoothing != smoothing {
+        state.smoothing = smoothing;
+        if smoothing == 0 {
+            output.write_all("s off\n".as_bytes())?;
+        } else {
+            let line : String = format!("s {}\n",smoothing);
+            output.write_all(line.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+// Convert an index of `data` into the index written in a `f` statement.
+pub fn write_index(i : usize, len : usize, relative : bool) -> String {
+    if relative {
+        (i as isize - len as isize).to_string()
+    } else {
+        (i+1).to_string()
+    }
+}
+
+// Index of the current object, an unnamed one is created if there is none.
+fn current_object(data : &mut ObjData, obj : &mut Option<usize>) -> usize {
+    match *obj {
+        Some(o) => o,
+        None => {
+            data.objects.push(Object::new(String::new()));
+            *obj = Some(data.objects.len()-1);
+            data.objects.len()-1
+        },
+    }
+}
+
+// Key to compare floats in a `HashMap`, adding 0 makes -0 and 0 equal.
+#[cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+pub fn float_key(v : Float) -> u64 {
+    (v + 0.).to_bits() as u64
+}
+
+pub fn join_args(args : &[&str]) -> String {
+    let mut name = String::new();
+    let mut args_it = args.iter();
+    if let Some(arg) = args_it.next() {
+        name += arg;
+    }
+    for arg in args_it {
+        name += " ";
+        name += arg;
+    }
+    name
 }
 
 impl Group {
     pub fn new(n : String) -> Group {
         Group {
             name : n,
-            indexes : HashSet::new()
+            indexes : Vec::new(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         }
     }
 }
@@ -78,11 +638,268 @@ impl Object {
     pub fn new(n : String) -> Object {
         Object {
             name : n,
-            primitives : Vec::new()
+            primitives : Vec::new(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : Vec::new(),
+        }
+    }
+
+    /// Add `element` after the other elements of the object.
+    pub fn push(&mut self, element : Element) {
+        match element {
+            Element::Face(i) => self.primitives.push(i),
+            Element::Line(i) => self.lines.push(i),
+            Element::Point(i) => self.points.push(i),
+            Element::Curve(i) => self.curves.push(i),
+            Element::Curve2(i) => self.curves2.push(i),
+            Element::Surface(i) => self.surfaces.push(i),
+            Element::Unknown(_) => {},
+        }
+        self.elements.push(element);
+    }
+
+    /// Elements of the object in the order in which they are written: `elements`, or the
+    /// elements of each kind if it is empty.
+    pub fn written_elements(&self) -> Cow<'_,[Element]> {
+        if self.elements.is_empty() {
+            Cow::Owned(self.primitives.iter().map(|&i| Element::Face(i))
+                       .chain(self.lines.iter().map(|&i| Element::Line(i)))
+                       .chain(self.points.iter().map(|&i| Element::Point(i)))
+                       .chain(self.curves2.iter().map(|&i| Element::Curve2(i)))
+                       .chain(self.curves.iter().map(|&i| Element::Curve(i)))
+                       .chain(self.surfaces.iter().map(|&i| Element::Surface(i))).collect())
+        } else {
+            Cow::Borrowed(&self.elements)
         }
     }
 }
 
+// Visitor building an `ObjData` while loading a file.
+struct ObjDataBuilder {
+    data : ObjData,
+    actif_groups : Vec<usize>,
+    obj : Option<usize>,
+    material : Option<usize>,
+    smoothing : u32,
+}
+
+impl ObjDataBuilder {
+    fn new() -> ObjDataBuilder {
+        ObjDataBuilder {
+            data : ObjData::new(),
+            actif_groups : Vec::new(),
+            obj : None,
+            material : None,
+            smoothing : 0,
+        }
+    }
+}
+
+impl ObjVisitor for ObjDataBuilder {
+    fn vertex(&mut self, v : (Float,Float,Float,Float)) {
+        self.data.vertices.push(v);
+    }
+
+    fn normal(&mut self, vn : (Float,Float,Float)) {
+        self.data.normals.push(vn);
+    }
+
+    fn texcoord(&mut self, vt : (Float,Float,Float)) {
+        self.data.texcoords.push(vt);
+    }
+
+    fn parameter_vertex(&mut self, vp : (Float,Float,Float)) {
+        self.data.parameter_vertices.push(vp);
+    }
+
+    fn face(&mut self, vertices : &[FaceVertex]) {
+        let data = &mut self.data;
+        data.faces.push(vertices.iter().cloned());
+        data.face_materials.push(self.material);
+        data.smoothing_groups.push(self.smoothing);
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Face(data.faces.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].indexes.push(data.faces.len()-1);
+        }
+    }
+
+    fn line(&mut self, vertices : &[(usize,Option<usize>)]) {
+        let data = &mut self.data;
+        data.lines.push(vertices.to_vec());
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Line(data.lines.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].lines.push(data.lines.len()-1);
+        }
+    }
+
+    fn point(&mut self, vertices : &[usize]) {
+        let data = &mut self.data;
+        data.points.push(vertices.to_vec());
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Point(data.points.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].points.push(data.points.len()-1);
+        }
+    }
+
+    fn object(&mut self, name : &str) {
+        self.data.objects.push(Object::new(String::from(name)));
+        self.obj = Some(self.data.objects.len()-1);
+    }
+
+    fn group(&mut self, names : &[&str]) {
+        self.actif_groups.clear();
+        // Elements of the default group are in no group
+        for name in names.iter().filter(|&&name| name != "default") {
+            let mut found = false;
+            for (i,g) in self.data.groups.iter().enumerate() {
+                if g.name == *name {
+                    if !self.actif_groups.contains(&i) {
+                        self.actif_groups.push(i);
+                    }
+                    found = true;
+                }
+            }
+            if !found {
+                self.data.groups.push(Group::new(String::from(*name)));
+                self.actif_groups.push(self.data.groups.len()-1);
+            }
+        }
+    }
+
+    fn smoothing_group(&mut self, group : u32) {
+        self.smoothing = group;
+    }
+
+    fn material_library(&mut self, files : &[&str]) {
+        for file in files {
+            self.data.material_libraries.push(String::from(*file));
+        }
+    }
+
+    fn material(&mut self, name : &str) {
+        let materials = &mut self.data.materials;
+        self.material = match materials.iter().position(|m| m == name) {
+            _ if name.is_empty() => None,
+            Some(i) => Some(i),
+            None => {
+                materials.push(String::from(name));
+                Some(materials.len()-1)
+            },
+        };
+    }
+
+    fn curve(&mut self, curve : Curve) {
+        let data = &mut self.data;
+        data.curves.push(curve);
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Curve(data.curves.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].curves.push(data.curves.len()-1);
+        }
+    }
+
+    fn curve2(&mut self, curve : Curve2) {
+        let data = &mut self.data;
+        data.curves2.push(curve);
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Curve2(data.curves2.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].curves2.push(data.curves2.len()-1);
+        }
+    }
+
+    fn surface(&mut self, surface : Surface) {
+        let data = &mut self.data;
+        data.surfaces.push(surface);
+        data.surface_materials.push(self.material);
+        data.surface_smoothing_groups.push(self.smoothing);
+        let o = current_object(data,&mut self.obj);
+        data.objects[o].push(Element::Surface(data.surfaces.len()-1));
+        for g in self.actif_groups.iter() {
+            data.groups[*g].surfaces.push(data.surfaces.len()-1);
+        }
+    }
+
+    fn unknown(&mut self, text : &str, warning : LoadingError) {
+        let data = &mut self.data;
+        data.unknown_statements.push(UnknownStatement {
+            line : warning.context().map_or(0,|c| c.line),
+            text : String::from(text),
+            object : self.obj,
+            groups : self.actif_groups.clone(),
+        });
+        if let Some(o) = self.obj {
+            data.objects[o].push(Element::Unknown(data.unknown_statements.len()-1));
+        }
+        data.warnings.push(warning);
+    }
+}
+
+impl FromStr for ObjData {
+    type Err = LoadingError;
+
+    fn from_str(s : &str) -> Result<ObjData,LoadingError> {
+        ObjData::from_str(s)
+    }
+}
+
+impl fmt::Display for ObjData {
+    /// Write in wavefront format, giving `to_string`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\n").ok().unwrap();
+    /// assert_eq!("v 0 0 0 1\n",data.to_string());
+    /// ```
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        let mut output = Vec::new();
+        self.write(&mut output).map_err(|_| fmt::Error)?;
+        f.write_str(&String::from_utf8_lossy(&output))
+    }
+}
+
+impl Default for ObjData {
+    fn default() -> ObjData {
+        ObjData::new()
+    }
+}
+
+/// Position referenced by a face vertex.
+impl Index<VertexIndex> for ObjData {
+    type Output = (Float,Float,Float,Float);
+
+    fn index(&self, i : VertexIndex) -> &(Float,Float,Float,Float) {
+        &self.vertices[i.0]
+    }
+}
+
+/// Texture coordinates referenced by a face vertex.
+impl Index<TexCoordIndex> for ObjData {
+    type Output = (Float,Float,Float);
+
+    fn index(&self, i : TexCoordIndex) -> &(Float,Float,Float) {
+        &self.texcoords[i.0]
+    }
+}
+
+/// Normal referenced by a face vertex.
+impl Index<NormalIndex> for ObjData {
+    type Output = (Float,Float,Float);
+
+    fn index(&self, i : NormalIndex) -> &(Float,Float,Float) {
+        &self.normals[i.0]
+    }
+}
 
 impl ObjData {
     /// Constructs a new empty `ObjData`.
@@ -99,13 +916,27 @@ impl ObjData {
             vertices : Vec::new(),
             normals : Vec::new(),
             texcoords : Vec::new(),
-            faces : Vec::new(),
+            faces : Faces::new(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            parameter_vertices : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
             objects : Vec::new(),
             groups : Vec::new(),
+            material_libraries : Vec::new(),
+            materials : Vec::new(),
+            face_materials : Vec::new(),
+            smoothing_groups : Vec::new(),
+            surface_materials : Vec::new(),
+            surface_smoothing_groups : Vec::new(),
+            unknown_statements : Vec::new(),
+            warnings : Vec::new(),
         }
     }
 
-    /// Load an `ObjData` from a `BufReader`.
+    /// Load an `ObjData` from any `BufRead`, like a `BufReader` or a `Cursor`.
     ///
     /// # Examples
     ///
@@ -118,132 +949,196 @@ impl ObjData {
     /// let mut input = BufReader::new(f);
     /// let data = ObjData::load(&mut input).ok().unwrap();
     /// ```
-    pub fn load<R : io::Read>(input : &mut io::BufReader<R>) -> Result<ObjData,LoadingError> {
-        let mut data = ObjData::new();
-        let mut buf = String::new();
-        let mut nb : usize = 0;
-        let mut actif_groups : Vec<usize> = Vec::new();
-        let mut obj : Option<usize> = None;
-        while try!(input.read_line(&mut buf)) > 0 {
-            // Skip comment
-            if buf.chars().next().unwrap() != '#' {
-                let mut iter = buf.split_whitespace();
-                let identifier = iter.next();
-                let args : Vec<_> = iter.collect();
-                if identifier.is_none() {continue;}
-                match identifier.unwrap() {
-                    "v" => {
-                        let values = try!(parse::<f32>(args,nb));
-                        if values.len() == 4 {
-                            data.vertices.push((values[0],values[1],values[2],values[3]));
-                        } else if values.len() == 3 {
-                            data.vertices.push((values[0],values[1],values[2],1.0));
-                        } else {
-                            return Err(LoadingError::WrongNumberOfArguments(nb));
-                        }
-                    },
-                    "vn" => {
-                        let values = try!(parse::<f32>(args,nb));
-                        if values.len() == 3 {
-                            data.normals.push((values[0],values[1],values[2]));
-                        } else {
-                            return Err(LoadingError::WrongNumberOfArguments(nb));
-                        }
-                    },
-                    "vt" => {
-                        let values = try!(parse::<f32>(args,nb));
-                        if values.len() == 3 {
-                            data.texcoords.push((values[0],values[1],values[2]));
-                        } else if values.len() == 2 {
-                            data.texcoords.push((values[0],values[1],0.));
-                        } else if values.len() == 1 {
-                            data.texcoords.push((values[0],0.,0.));
-                        } else {
-                            return Err(LoadingError::WrongNumberOfArguments(nb));
-                        }
-                    },
-                    "s" => {
-                        // Not supported
-                    },
-                    "f" => {
-                        let mut vec : Vec<(usize,Option<usize>,Option<usize>)> = Vec::new();
-                        if args.len() < 3 {return Err(LoadingError::WrongNumberOfArguments(nb))}
-                        for arg in args {
-                            let index : Vec<_> = arg.split('/').collect();
-                            if index.len() == 0 || index.len() > 3 {
-                                return Err(LoadingError::WrongNumberOfArguments(nb));
-                            }
-                            let v = match index[0].parse::<usize>() {
-                                Ok(val) => val-1,
-                                Err(_) => return Err(LoadingError::Parse(nb)),
-                            };
-                            let mut vt = None;
-                            if index.len() >= 2 {
-                                vt = match index[1].parse::<usize>().ok() {
-                                    Some(val) => Some(val-1),
-                                    None => None,
-                                };
-                            }
-                            let mut vn = None;
-                            if index.len() == 3 {
-                                vn = match index[2].parse::<usize>().ok() {
-                                    Some(val) => Some(val-1),
-                                    None => None,
-                                };
-                            }
-                            vec.push((v,vt,vn));
-                        }
-                        data.faces.push(vec);
-                        if obj.is_none() {
-                            data.objects.push(Object::new(String::new()));
-                            obj = Some(data.objects.len()-1);
-                        }
-                        data.objects[obj.unwrap()].primitives.push(data.faces.len()-1);
-                        for g in actif_groups.iter() {
-                            data.groups[*g].indexes.insert(data.faces.len()-1);
-                        }
-                    },
-                    "o" => {
-                        if args.len() == 0 {
-                            return Err(LoadingError::WrongNumberOfArguments(nb));
-                        }
-                        let mut name = String::new();
-                        let mut args_it = args.iter();
-                        name += args_it.next().unwrap();
-                        for arg in args_it {
-                            name += " ";
-                            name += arg;
-                        }
-                        data.objects.push(Object::new(String::from(name)));
-                        obj = Some(data.objects.len()-1);
-                    },
-                    "g" => {
-                        actif_groups.clear();
-                        for arg in args {
-                            let mut found = false;
-                            for (i,g) in data.groups.iter().enumerate() {
-                                if g.name == arg {
-                                    actif_groups.push(i);
-                                    found = true;
-                                }
-                            }
-                            if !found {
-                                data.groups.push(Group::new(String::from(arg)));
-                                actif_groups.push(data.groups.len()-1);
-                            }
-                        }
-                    },
-                    _ => return Err(LoadingError::InvalidLine(nb)),
-                }
-            }
-            nb += 1;
-            buf.clear();
+    pub fn load<R : io::BufRead>(input : &mut R) -> Result<ObjData,LoadingError> {
+        ObjData::load_with_options(input,&LoadOptions::default())
+    }
+
+    /// Load an `ObjData` from any `BufRead` with `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::{ObjData,LoadOptions};
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nbevel on\n".as_bytes());
+    /// let options = LoadOptions { lenient : true };
+    /// let data = ObjData::load_with_options(&mut input,&options).ok().unwrap();
+    /// assert_eq!("bevel on",data.unknown_statements[0].text);
+    /// assert_eq!(1,data.warnings.len());
+    /// ```
+    pub fn load_with_options<R : io::BufRead>(input : &mut R, options : &LoadOptions) -> Result<ObjData,LoadingError> {
+        let mut builder = ObjDataBuilder::new();
+        visit(input,options,&mut builder)?;
+        Ok(builder.data)
+    }
+
+    /// Load an `ObjData` from the file at `path`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_path("cube.obj").ok().unwrap();
+    /// assert_eq!(8,data.vertices.len());
+    /// ```
+    pub fn from_path<P : AsRef<Path>>(path : P) -> Result<ObjData,LoadingError> {
+        let mut input = io::BufReader::new(open(path.as_ref())?);
+        ObjData::load(&mut input)
+    }
+
+    /// Load an `ObjData` from the file at `path` with the material libraries it uses.
+    ///
+    /// Each library of `material_libraries` is resolved relative to the directory of `path`.
+    pub fn from_path_with_materials<P : AsRef<Path>>(path : P) -> Result<(ObjData,Vec<MtlData>),LoadingError> {
+        let path = path.as_ref();
+        let data = ObjData::from_path(path)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut libraries = Vec::new();
+        for lib in &data.material_libraries {
+            libraries.push(MtlData::from_path(dir.join(lib))?);
+        }
+        Ok((data,libraries))
+    }
+
+    /// Load an `ObjData` from bytes in memory.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_bytes(b"v 0 0 0\n").ok().unwrap();
+    /// assert_eq!(1,data.vertices.len());
+    /// ```
+    pub fn from_bytes(bytes : &[u8]) -> Result<ObjData,LoadingError> {
+        ObjData::parse_bytes(bytes)
+    }
+
+    /// Load an `ObjData` from bytes in memory, reading vertices and faces without allocating.
+    ///
+    /// This is faster than `load` for large meshes and gives the same data and errors.
+    /// Lines can end with `\n` or `\r\n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::{ObjData,VertexIndex};
+    ///
+    /// let data = ObjData::parse_bytes(b"v 0 0 0\r\nv 1 0 0\r\nv 0 1 0\r\nf 1 2 3\r\n").ok().unwrap();
+    /// assert_eq!(3,data.vertices.len());
+    /// let positions : Vec<_> = data.faces.get(0).unwrap().positions().collect();
+    /// assert_eq!(vec![VertexIndex(0),VertexIndex(1),VertexIndex(2)],positions);
+    /// ```
+    pub fn parse_bytes(bytes : &[u8]) -> Result<ObjData,LoadingError> {
+        ObjData::parse_bytes_with_options(bytes,&LoadOptions::default())
+    }
+
+    /// Load an `ObjData` from bytes in memory with `options`, like `parse_bytes`.
+    pub fn parse_bytes_with_options(bytes : &[u8], options : &LoadOptions) -> Result<ObjData,LoadingError> {
+        let mut builder = ObjDataBuilder::new();
+        visit_bytes(bytes,options,&mut builder)?;
+        Ok(builder.data)
+    }
+
+    /// Load an `ObjData` from bytes in memory like `parse_bytes`, parsing on `threads` threads.
+    ///
+    /// The data and errors are the same as with `parse_bytes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs;
+    /// use std::thread;
+    /// use lwobj::ObjData;
+    ///
+    /// let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    /// let data = ObjData::parse_bytes_parallel(&fs::read("cube.obj").unwrap(),threads).ok().unwrap();
+    /// assert_eq!(12,data.faces.len());
+    /// ```
+    pub fn parse_bytes_parallel(bytes : &[u8], threads : usize) -> Result<ObjData,LoadingError> {
+        ObjData::parse_bytes_parallel_with_options(bytes,threads,&LoadOptions::default())
+    }
+
+    /// Load an `ObjData` from bytes in memory with `options`, like `parse_bytes_parallel`.
+    pub fn parse_bytes_parallel_with_options(bytes : &[u8], threads : usize, options : &LoadOptions) -> Result<ObjData,LoadingError> {
+        let mut builder = ObjDataBuilder::new();
+        visit_bytes_parallel(bytes,options,threads,&mut builder)?;
+        Ok(builder.data)
+    }
+
+    /// Load an `ObjData` from a string, also available through `str::parse`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\nv 1 0 0\n").ok().unwrap();
+    /// assert_eq!(2,data.vertices.len());
+    /// let data : ObjData = "v 0 0 0\n".parse().ok().unwrap();
+    /// assert_eq!(1,data.vertices.len());
+    /// ```
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s : &str) -> Result<ObjData,LoadingError> {
+        ObjData::from_bytes(s.as_bytes())
+    }
+
+    /// Smoothing group of the face `i`, `None` if smoothing is off for this face.
+    ///
+    /// Faces sharing a smoothing group should share their vertex normals.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\ns 1\nf 1 2 3\n".as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// assert_eq!(Some(1),data.smoothing_group(0));
+    /// ```
+    pub fn smoothing_group(&self, i : usize) -> Option<u32> {
+        match self.smoothing_groups.get(i) {
+            Some(&s) if s != 0 => Some(s),
+            _ => None,
         }
-        return Ok(data);
+    }
+
+    /// Positions of the vertices of `face`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3\n").ok().unwrap();
+    /// let face = data.faces.get(0).unwrap();
+    /// let positions : Vec<_> = data.face_positions(face).collect();
+    /// assert_eq!(vec![&(0.,0.,0.,1.),&(1.,0.,0.,1.),&(0.,1.,0.,1.)],positions);
+    /// let normals : Vec<_> = data.face_normals(face).collect();
+    /// assert_eq!(vec![Some(&(0.,0.,1.)),Some(&(0.,0.,1.)),None],normals);
+    /// assert_eq!((1.,0.,0.,1.),data[face.vertices[1].position]);
+    /// ```
+    pub fn face_positions<'a>(&'a self, face : Face<'a>) -> impl Iterator<Item=&'a (Float,Float,Float,Float)> + 'a {
+        face.positions().map(move |i| &self[i])
+    }
+
+    /// Texture coordinates of the vertices of `face`, `None` for vertices without some.
+    pub fn face_texcoords<'a>(&'a self, face : Face<'a>) -> impl Iterator<Item=Option<&'a (Float,Float,Float)>> + 'a {
+        face.texcoords().map(move |i| i.map(|i| &self[i]))
+    }
+
+    /// Normals of the vertices of `face`, `None` for vertices without one.
+    pub fn face_normals<'a>(&'a self, face : Face<'a>) -> impl Iterator<Item=Option<&'a (Float,Float,Float)>> + 'a {
+        face.normals().map(move |i| i.map(|i| &self[i]))
     }
 
     /// Write in wavefront format in file.
     ///
+    /// `output` receives many small writes and should be buffered.
+    ///
     /// # Examples
     ///
     /// ```
@@ -259,67 +1154,248 @@ impl ObjData {
     /// let mut output = BufWriter::new(f2);
     /// assert!(data.write(&mut output).is_ok());
     /// ```
-    pub fn write<W : io::Write>(&self, output : &mut io::BufWriter<W>) -> Result<(),LoadingError> {
+    pub fn write<W : io::Write>(&self, output : &mut W) -> Result<(),LoadingError> {
+        self.write_with_options(output,&WriteOptions::default())
+    }
+
+    /// Write in wavefront format in file with `options`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::fs::File;
+    /// use std::io::BufWriter;
+    /// use std::io::BufReader;
+    /// use lwobj::{ObjData,WriteOptions};
+    ///
+    /// let f1 = File::open("cube.obj").unwrap();
+    /// let mut input = BufReader::new(f1);
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let mut output = BufWriter::new(Vec::<u8>::new());
+    /// let options = WriteOptions { relative_indices : true };
+    /// assert!(data.write_with_options(&mut output,&options).is_ok());
+    /// ```
+    pub fn write_with_options<W : io::Write>(&self, output : &mut W, options : &WriteOptions) -> Result<(),LoadingError> {
+        let relative = options.relative_indices;
+        // Write material libraries
+        if !self.material_libraries.is_empty() {
+            output.write_all("mtllib".as_bytes())?;
+            for lib in &self.material_libraries {
+                output.write_all(" ".as_bytes())?;
+                output.write_all(lib.as_bytes())?;
+            }
+            output.write_all("\n".as_bytes())?;
+        }
+
         // Write vertices
         for &(x,y,z,w) in &self.vertices {
             let line : String = format!("v {} {} {} {}\n",x,y,z,w);
-            try!(output.write_all(line.as_bytes()));
+            output.write_all(line.as_bytes())?;
         }
 
         // Write normals
         for &(x,y,z) in &self.normals {
             let line : String = format!("vn {} {} {}\n",x,y,z);
-            try!(output.write_all(line.as_bytes()));
+            output.write_all(line.as_bytes())?;
         }
 
         // Write texcoords
         for &(u,v,w) in &self.texcoords {
             let line : String = format!("vt {} {} {}\n",u,v,w);
-            try!(output.write_all(line.as_bytes()));
+            output.write_all(line.as_bytes())?;
+        }
+
+        // Write parameter space vertices
+        for &(u,v,w) in &self.parameter_vertices {
+            let line : String = format!("vp {} {} {}\n",u,v,w);
+            output.write_all(line.as_bytes())?;
         }
 
-        // Write faces
-        let mut actif_groups : Vec<usize> = Vec::new();
-        for o in &self.objects {
+        // Write the elements of each object
+        let elements : Vec<Cow<[Element]>> = self.objects.iter().map(|o| o.written_elements()).collect();
+        let orphans = self.freeform_orphans(&elements);
+        let curves2 = elements.iter().flat_map(|e| e.iter()).chain(orphans.iter()).filter_map(|e| match *e {
+            Element::Curve2(i) => Some(i),
+            _ => None,
+        });
+        let mut state = WriteState {
+            relative,
+            groups : Vec::new(),
+            material : None,
+            smoothing : 0,
+            freeform : FreeFormWriter::new(self.curves2.len(),curves2),
+            face_groups : Membership::new(self.faces.len(),self.groups.iter().map(|g| &g.indexes[..])),
+            line_groups : Membership::new(self.lines.len(),self.groups.iter().map(|g| &g.lines[..])),
+            point_groups : Membership::new(self.points.len(),self.groups.iter().map(|g| &g.points[..])),
+            curve_groups : Membership::new(self.curves.len(),self.groups.iter().map(|g| &g.curves[..])),
+            curve2_groups : Membership::new(self.curves2.len(),self.groups.iter().map(|g| &g.curves2[..])),
+            surface_groups : Membership::new(self.surfaces.len(),self.groups.iter().map(|g| &g.surfaces[..])),
+        };
+        // Unknown statements placed among the elements are written there, the others are
+        // written before the objects or at the end of their object
+        let mut placed = vec![false; self.unknown_statements.len()];
+        for element in elements.iter().flat_map(|e| e.iter()) {
+            if let Element::Unknown(i) = *element {
+                if i < placed.len() {
+                    placed[i] = true;
+                }
+            }
+        }
+        self.write_unknown_statements(output,&mut state.groups,None,&placed)?;
+        for (j,o) in self.objects.iter().enumerate() {
             if o.name != String::new() {
                 let line : String = format!("o {}\n",o.name);
-                try!(output.write_all(line.as_bytes()));
+                output.write_all(line.as_bytes())?;
             }
-            for i in &o.primitives {
-                let mut groups : Vec<usize> = Vec::new();
-                for (j,g) in self.groups.iter().enumerate() {
-                    if g.indexes.contains(i) {
-                        groups.push(j);
-                    }
-                }
-                if actif_groups != groups {
-                    actif_groups = groups;
-                    try!(output.write_all("g".as_bytes()));
-                    for g in &actif_groups {
-                        try!(output.write_all(" ".as_bytes()));
-                        try!(output.write_all(&self.groups[*g].name.as_bytes()));
-                    }
-                    try!(output.write_all("\n".as_bytes()));
-                }
+            for &element in elements[j].iter() {
+                self.write_element(output,&mut state,element)?;
+            }
+            self.write_unknown_statements(output,&mut state.groups,Some(j),&placed)?;
+        }
+
+        // Write free-form curves and surfaces in no object
+        for &element in &orphans {
+            self.write_element(output,&mut state,element)?;
+        }
+        Ok(())
+    }
 
-                try!(output.write_all("f".as_bytes()));
-                for &(v,vt,vn) in &self.faces[*i] {
-                    let vt_str = match vt {
-                        Some(val) => (val+1).to_string(),
+    // Free-form elements which are in no object, curves in parameter space first as surfaces reference them.
+    fn freeform_orphans(&self, elements : &[Cow<[Element]>]) -> Vec<Element> {
+        let mut curves = vec![false; self.curves.len()];
+        let mut curves2 = vec![false; self.curves2.len()];
+        let mut surfaces = vec![false; self.surfaces.len()];
+        for element in elements.iter().flat_map(|e| e.iter()) {
+            match *element {
+                Element::Curve(i) if i < curves.len() => curves[i] = true,
+                Element::Curve2(i) if i < curves2.len() => curves2[i] = true,
+                Element::Surface(i) if i < surfaces.len() => surfaces[i] = true,
+                _ => {},
+            }
+        }
+        let orphans = |found : Vec<bool>| found.into_iter().enumerate().filter(|&(_,found)| !found).map(|(i,_)| i);
+        orphans(curves2).map(Element::Curve2)
+            .chain(orphans(curves).map(Element::Curve))
+            .chain(orphans(surfaces).map(Element::Surface)).collect()
+    }
+
+    // Write `element` preceded by the statements changing the active groups, material and smoothing group.
+    fn write_element<W : io::Write>(&self, output : &mut W, state : &mut WriteState, element : Element) -> Result<(),LoadingError> {
+        let relative = state.relative;
+        match element {
+            Element::Face(i) => {
+                self.write_groups(output,&mut state.groups,state.face_groups.groups(i))?;
+                self.write_material(output,state,self.face_materials.get(i).and_then(|m| *m))?;
+                write_smoothing_group(output,state,self.smoothing_groups.get(i).cloned().unwrap_or(0))?;
+                output.write_all("f".as_bytes())?;
+                for vertex in &self.faces[i] {
+                    let v_str = write_index(vertex.position.0,self.vertices.len(),relative);
+                    let vt_str = match vertex.texcoord {
+                        Some(val) => write_index(val.0,self.texcoords.len(),relative),
                         None => "".to_string(),
                     };
-                    let vn_str = match vn {
-                        Some(val) => (val+1).to_string(),
+                    let vn_str = match vertex.normal {
+                        Some(val) => write_index(val.0,self.normals.len(),relative),
                         None => "".to_string(),
                     };
-                    let arg : String = format!(" {}/{}/{}",v+1,vt_str,vn_str);
-                    try!(output.write_all(arg.as_bytes()));
+                    let arg : String = format!(" {}/{}/{}",v_str,vt_str,vn_str);
+                    output.write_all(arg.as_bytes())?;
+                }
+                output.write_all("\n".as_bytes())?;
+            },
+            Element::Line(i) => {
+                self.write_groups(output,&mut state.groups,state.line_groups.groups(i))?;
+                output.write_all("l".as_bytes())?;
+                for &(v,vt) in &self.lines[i] {
+                    let arg : String = match vt {
+                        Some(val) => format!(" {}/{}",write_index(v,self.vertices.len(),relative),
+                                                      write_index(val,self.texcoords.len(),relative)),
+                        None => format!(" {}",write_index(v,self.vertices.len(),relative)),
+                    };
+                    output.write_all(arg.as_bytes())?;
+                }
+                output.write_all("\n".as_bytes())?;
+            },
+            Element::Point(i) => {
+                self.write_groups(output,&mut state.groups,state.point_groups.groups(i))?;
+                output.write_all("p".as_bytes())?;
+                for &v in &self.points[i] {
+                    let arg : String = format!(" {}",write_index(v,self.vertices.len(),relative));
+                    output.write_all(arg.as_bytes())?;
                 }
-                try!(output.write_all("\n".as_bytes()));
+                output.write_all("\n".as_bytes())?;
+            },
+            Element::Curve(i) => {
+                self.write_groups(output,&mut state.groups,state.curve_groups.groups(i))?;
+                state.freeform.curve(self,output,i,relative)?;
+            },
+            Element::Curve2(i) => {
+                self.write_groups(output,&mut state.groups,state.curve2_groups.groups(i))?;
+                state.freeform.curve2(self,output,i,relative)?;
+            },
+            Element::Surface(i) => {
+                self.write_groups(output,&mut state.groups,state.surface_groups.groups(i))?;
+                self.write_material(output,state,self.surface_materials.get(i).and_then(|m| *m))?;
+                write_smoothing_group(output,state,self.surface_smoothing_groups.get(i).cloned().unwrap_or(0))?;
+                state.freeform.surface(self,output,i,relative)?;
+            },
+            Element::Unknown(i) => self.write_unknown_statement(output,&mut state.groups,i)?,
+        }
+        Ok(())
+    }
+
+    // Write a `usemtl` statement if `material` differs from the active one.
+    // An element without material after one with a material is preceded by `usemtl` alone.
+    fn write_material<W : io::Write>(&self, output : &mut W, state : &mut WriteState, material : Option<usize>) -> Result<(),LoadingError> {
+        if state.material != material {
+            state.material = material;
+            let line : String = match material {
+                Some(m) => format!("usemtl {}\n",self.materials[m]),
+                None => String::from("usemtl\n"),
+            };
+            output.write_all(line.as_bytes())?;
+        }
+        Ok(())
+    }
+
+    // Write a `g` statement if the groups containing an element differ from the active ones.
+    // Elements in no group are in the group `default`.
+    fn write_groups<W : io::Write>(&self, output : &mut W, actif_groups : &mut Vec<usize>, groups : &[usize]) -> Result<(),LoadingError> {
+        if actif_groups[..] != *groups {
+            *actif_groups = groups.to_vec();
+            output.write_all("g".as_bytes())?;
+            for g in actif_groups.iter() {
+                output.write_all(" ".as_bytes())?;
+                output.write_all(self.groups[*g].name.as_bytes())?;
+            }
+            if actif_groups.is_empty() {
+                output.write_all(" default".as_bytes())?;
             }
+            output.write_all("\n".as_bytes())?;
         }
         Ok(())
     }
+
+    // Write the unknown statements of an object which are not `placed` among its elements.
+    fn write_unknown_statements<W : io::Write>(&self, output : &mut W, actif_groups : &mut Vec<usize>,
+                                               object : Option<usize>, placed : &[bool]) -> Result<(),LoadingError> {
+        for (i,statement) in self.unknown_statements.iter().enumerate() {
+            if statement.object == object && !placed[i] {
+                self.write_unknown_statement(output,actif_groups,i)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Write the unknown statement `i` with its groups.
+    fn write_unknown_statement<W : io::Write>(&self, output : &mut W, actif_groups : &mut Vec<usize>,
+                                              i : usize) -> Result<(),LoadingError> {
+        let statement = &self.unknown_statements[i];
+        self.write_groups(output,actif_groups,&statement.groups)?;
+        output.write_all(statement.text.as_bytes())?;
+        output.write_all("\n".as_bytes())?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -328,6 +1404,22 @@ mod tests {
     use std::io::BufWriter;
     use std::str;
     use obj::*;
+    use face::faces;
+
+    // Add the vertices, texture coordinates and normals referenced by the faces of the tests.
+    fn with_elements(obj_str : &str) -> String {
+        let mut s = String::new();
+        for _ in 0..9 {
+            s += "v 0 0 0\n";
+        }
+        for _ in 0..7 {
+            s += "vt 0 0\n";
+        }
+        for _ in 0..3 {
+            s += "vn 0 0 1\n";
+        }
+        s + obj_str
+    }
 
     #[test]
     fn load_invalid_line() {
@@ -340,14 +1432,14 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::InvalidLine(line) => assert!(line == 1),
-            _ => assert!(false),
+            LoadingError::InvalidLine(context) => assert!(context.line == 2),
+            _ => panic!(),
         };
     }
 
     #[test]
     fn load_vertices() {
-        let expected = vec![(1.,-2.,-3.5,1f32),
+        let expected = vec![(1.,-2.,-3.5,1. as Float),
         (1.,-1.,1.,1.),
         (-1.,-1.,1.,0.5),
         (-1.,-1.,-1.,1.)];
@@ -374,8 +1466,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
         };
     }
 
@@ -390,8 +1482,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::Parse(line) => assert!(line == 3),
-            _ => assert!(false),
+            LoadingError::Parse(context) => assert!(context.line == 4),
+            _ => panic!(),
         };
     }
 
@@ -424,8 +1516,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
         };
 
         let obj_str =
@@ -437,8 +1529,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
         };
     }
 
@@ -446,7 +1538,7 @@ mod tests {
     fn load_texcoords() {
         let expected = vec![(0.,1.,0.),
         (0.,0.5,0.),
-        (0f32,1f32,1f32),
+        (0.,1.,1. as Float),
         (1.,1.,0.5)];
         let obj_str =
         r#"o Test
@@ -462,12 +1554,12 @@ mod tests {
 
     #[test]
     fn load_faces() {
-        let expected = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        let expected = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
         vec![(7,None,None), (5,None,None), (4,None,None)],
         vec![(3,None,None), (4,None,None), (5,None,None)],
         vec![(7,Some(2),Some(1)), (5,Some(4),Some(2)), (4,Some(6),Some(0))],
         vec![(8,Some(3),None), (6,Some(2),None), (2,Some(1),None)],
-        ];
+        ]);
         let obj_str =
         r#"o Test
         f 2//1 4//1 1//1
@@ -476,6 +1568,91 @@ mod tests {
         f 8/3/2 6/5/3 5/7/1
         f 9/4/ 7/3/ 3/2/"#;
 
+        let obj_str = with_elements(obj_str);
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(expected,data.faces);
+    }
+
+    #[test]
+    fn load_faces_relative_indices() {
+        let expected = faces(vec![ vec![(0,None,Some(1)), (1,None,Some(1)), (2,None,Some(0))],
+        vec![(3,Some(0),None), (1,Some(1),None), (2,Some(1),None)],
+        ]);
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vn 0 0 1
+        vn 0 0 -1
+        f -3//-1 -2//2 -1//-2
+        vt 0 0
+        vt 1 1
+        v 0 1 0
+        f -1/-2 2/-1 -2/2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(expected,data.faces);
+    }
+
+    #[test]
+    fn load_faces_index_out_of_range() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        f -1 -2 -3"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((3,IndexKind::Vertex,-3),(context.line,kind,index));
+            },
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        f 0 1 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((4,IndexKind::Vertex,0),(context.line,kind,index));
+            },
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        vn 0 0 1
+        f 1//1 2//1 3//1
+        f 1//1 2//2 3//1
+        f 1//1 2//3 3//1
+        v 0 1 0"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((7,IndexKind::Normal,3),(context.line,kind,index));
+            },
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_faces_forward_reference() {
+        let expected = faces(vec![ vec![(0,None,None), (1,None,None), (2,None,None)]]);
+        let obj_str =
+        r#"f 1 2 3
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0"#;
+
         let mut input = BufReader::new(obj_str.as_bytes());
         let data = ObjData::load(&mut input).ok().unwrap();
         assert_eq!(expected,data.faces);
@@ -492,8 +1669,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 3),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 4),
+            _ => panic!(),
         };
 
         let obj_str =
@@ -506,8 +1683,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
         };
     }
 
@@ -521,8 +1698,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::Parse(line) => assert!(line == 2),
-            _ => assert!(false),
+            LoadingError::Parse(context) => assert!(context.line == 3),
+            _ => panic!(),
         };
     }
 
@@ -536,8 +1713,8 @@ mod tests {
 
         let mut input = BufReader::new(obj_str.as_bytes());
         match ObjData::load(&mut input).err().unwrap() {
-            LoadingError::WrongNumberOfArguments(line) => assert!(line == 0),
-            _ => assert!(false),
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 1),
+            _ => panic!(),
         };
     }
 
@@ -545,7 +1722,13 @@ mod tests {
     fn load_unamed_object() {
         let obj = Object {
             name : String::from(""),
-            primitives : vec![0,1,2,3,4]
+            primitives : vec![0,1,2,3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3),Element::Face(4)],
         };
         let expected = vec![obj];
         let obj_str =
@@ -555,6 +1738,7 @@ mod tests {
         f 8/3/2 6/5/3 5/7/1
         f 9/4/ 7/3/ 3/2/"#;
 
+        let obj_str = with_elements(obj_str);
         let mut input = BufReader::new(obj_str.as_bytes());
         let data = ObjData::load(&mut input).ok().unwrap();
         assert_eq!(expected,data.objects);
@@ -564,7 +1748,13 @@ mod tests {
     fn load_object() {
         let obj = Object {
             name : String::from("Cube"),
-            primitives : vec![0,1,2,3,4]
+            primitives : vec![0,1,2,3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3),Element::Face(4)],
         };
         let expected = vec![obj];
         let obj_str =
@@ -575,6 +1765,7 @@ mod tests {
         f 8/3/2 6/5/3 5/7/1
         f 9/4/ 7/3/ 3/2/"#;
 
+        let obj_str = with_elements(obj_str);
         let mut input = BufReader::new(obj_str.as_bytes());
         let data = ObjData::load(&mut input).ok().unwrap();
         assert_eq!(expected,data.objects);
@@ -584,15 +1775,33 @@ mod tests {
     fn load_several_objects() {
         let obj1 = Object {
             name : String::from(""),
-            primitives : vec![0,1,2,]
+            primitives : vec![0,1,2,],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2)],
         };
         let obj2 = Object {
             name : String::from("Cube"),
-            primitives : vec![3,4]
+            primitives : vec![3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(3),Element::Face(4)],
         };
         let obj3 = Object {
             name : String::from("Test"),
-            primitives : vec![5]
+            primitives : vec![5],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(5)],
         };
         let expected = vec![obj1,obj2,obj3];
         let obj_str =
@@ -605,6 +1814,7 @@ mod tests {
         o Test
         f 4 3 5"#;
 
+        let obj_str = with_elements(obj_str);
         let mut input = BufReader::new(obj_str.as_bytes());
         let data = ObjData::load(&mut input).ok().unwrap();
         assert_eq!(expected,data.objects);
@@ -614,15 +1824,30 @@ mod tests {
     fn load_group() {
         let gr1 = Group {
             name : String::from("gr1"),
-            indexes : vec!(0,1,2,3).into_iter().collect()
+            indexes : vec!(0,1,2,3).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         let gr2 = Group {
             name : String::from("gr2"),
-            indexes : vec!(0,1,5).into_iter().collect()
+            indexes : vec!(0,1,5).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         let gr3 = Group {
             name : String::from("gr3"),
-            indexes : vec!(4).into_iter().collect()
+            indexes : vec!(4).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         let expected = vec![gr1,gr2,gr3];
         let obj_str =
@@ -637,15 +1862,318 @@ mod tests {
         g gr2
         f 9/4/ 7/3/ 3/2/"#;
 
+        let obj_str = with_elements(obj_str);
         let mut input = BufReader::new(obj_str.as_bytes());
         let data = ObjData::load(&mut input).ok().unwrap();
         assert_eq!(expected,data.groups);
     }
 
+    #[test]
+    fn load_write_group() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\n\
+                       g gr1 gr1 gr2\nf 1 2 3\nl 1 2\ng gr2\nf 1 2 3\np 1\ng gr2 gr1\nf 1 2 3\nl 2 3\ng default\np 2\n";
+        let data = ObjData::load(&mut obj_str.as_bytes()).ok().unwrap();
+        assert_eq!(vec![0,2],data.groups[0].indexes);
+        assert_eq!(vec![0,1],data.groups[0].lines);
+        assert_eq!(vec![0,1,2],data.groups[1].indexes);
+        assert_eq!(vec![0],data.groups[1].points);
+        assert_eq!(2,data.groups.len());
+
+        let expected = "v 0 0 0 1\nv 1 0 0 1\nv 0 1 0 1\n\
+                        g gr1 gr2\nf 1// 2// 3//\nl 1 2\ng gr2\nf 1// 2// 3//\np 1\n\
+                        g gr1 gr2\nf 1// 2// 3//\nl 2 3\ng default\np 2\n";
+        let mut output = Vec::new();
+        assert!(data.write(&mut output).is_ok());
+        assert_eq!(expected,str::from_utf8(&output).unwrap());
+        let reloaded = ObjData::load(&mut expected.as_bytes()).ok().unwrap();
+        assert_eq!(data.groups,reloaded.groups);
+    }
+
+    #[test]
+    fn load_materials() {
+        let obj_str =
+        r#"mtllib cube.mtl extra.mtl
+        f 2//1 4//1 1//1
+        usemtl Material
+        f 8 6 5
+        f 4// 5// 6//
+        usemtl Other Material
+        f 8/3/2 6/5/3 5/7/1
+        usemtl Material
+        f 9/4/ 7/3/ 3/2/
+        usemtl
+        f 1 2 3"#;
+
+        let obj_str = with_elements(obj_str);
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![String::from("cube.mtl"),String::from("extra.mtl")],data.material_libraries);
+        assert_eq!(vec![String::from("Material"),String::from("Other Material")],data.materials);
+        assert_eq!(vec![None,Some(0),Some(0),Some(1),Some(0),None],data.face_materials);
+    }
+
+    #[test]
+    fn load_materials_wrong_number_of_arguments() {
+        let obj_str =
+        r#"mtllib
+        usemtl Material
+        f 2//1 4//1 1//1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 1),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_smoothing_groups() {
+        let obj_str =
+        r#"f 2//1 4//1 1//1
+        s 1
+        f 8 6 5
+        f 4// 5// 6//
+        s off
+        f 8/3/2 6/5/3 5/7/1
+        s 3
+        f 9/4/ 7/3/ 3/2/
+        s 0
+        f 9/4/ 7/3/ 3/2/"#;
+
+        let obj_str = with_elements(obj_str);
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![0,1,1,0,3,0],data.smoothing_groups);
+        assert_eq!(None,data.smoothing_group(0));
+        assert_eq!(Some(1),data.smoothing_group(1));
+        assert_eq!(Some(3),data.smoothing_group(4));
+    }
+
+    #[test]
+    fn load_smoothing_groups_err() {
+        let obj_str =
+        r#"v 0 0 0
+        s 1 2"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        s on"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_lines_and_points() {
+        let obj_str =
+        r#"f 2//1 4//1 1//1
+        l 1 2 3
+        g gr1
+        l 4/1 5/2
+        o Guides
+        p 6 7 -1
+        l -2/-1 -1/-2"#;
+
+        let obj_str = with_elements(obj_str);
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load(&mut input).ok().unwrap();
+        assert_eq!(vec![vec![(0,None),(1,None),(2,None)],
+        vec![(3,Some(0)),(4,Some(1))],
+        vec![(7,Some(6)),(8,Some(5))]],data.lines);
+        assert_eq!(vec![vec![5,6,8]],data.points);
+        let obj1 = Object {
+            name : String::from(""),
+            primitives : vec![0],
+            lines : vec![0,1],
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Line(0),Element::Line(1)],
+        };
+        let obj2 = Object {
+            name : String::from("Guides"),
+            primitives : Vec::new(),
+            lines : vec![2],
+            points : vec![0],
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Point(0),Element::Line(2)],
+        };
+        assert_eq!(vec![obj1,obj2],data.objects);
+        let gr1 = Group {
+            name : String::from("gr1"),
+            indexes : Vec::new(),
+            lines : vec!(1,2).into_iter().collect(),
+            points : vec!(0).into_iter().collect(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+        };
+        assert_eq!(vec![gr1],data.groups);
+    }
+
+    #[test]
+    fn load_lines_and_points_wrong_number_of_arguments() {
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        l 1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        v 1 0 0
+        l 1 2/1/1"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 3),
+            _ => panic!(),
+        };
+
+        let obj_str =
+        r#"v 0 0 0
+        p"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::WrongNumberOfArguments(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_lenient() {
+        let obj_str =
+        r#"v 0 0 0
+        bevel on
+        g gr1
+        o Test
+        f 1 1 1
+        lod 3
+        vendor_ext a   b"#;
+
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::InvalidLine(context) => assert!(context.line == 2),
+            _ => panic!(),
+        };
+
+        let expected = vec![UnknownStatement {
+            line : 2,
+            text : String::from("bevel on"),
+            object : None,
+            groups : Vec::new(),
+        },
+        UnknownStatement {
+            line : 6,
+            text : String::from("lod 3"),
+            object : Some(0),
+            groups : vec![0],
+        },
+        UnknownStatement {
+            line : 7,
+            text : String::from("vendor_ext a   b"),
+            object : Some(0),
+            groups : vec![0],
+        }];
+        let options = LoadOptions { lenient : true };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let data = ObjData::load_with_options(&mut input,&options).ok().unwrap();
+        assert_eq!(expected,data.unknown_statements);
+        assert_eq!(3,data.warnings.len());
+        match data.warnings[1] {
+            LoadingError::InvalidLine(ref context) => assert!(context.line == 6),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn load_write_unknown_statements() {
+        // Unknown statements are written back between the elements they were found
+        let obj_str = "v 0 0 0 1\nbevel on\no Test\nlod 3\nf 1// 1// 1//\nc_interp on\nf 1// 1// 1//\n";
+        let options = LoadOptions { lenient : true };
+        let data = ObjData::load_with_options(&mut obj_str.as_bytes(),&options).ok().unwrap();
+        assert_eq!(vec![Element::Unknown(1),Element::Face(0),Element::Unknown(2),Element::Face(1)],data.objects[0].elements);
+        let mut output = Vec::new();
+        assert!(data.write(&mut output).is_ok());
+        assert_eq!(obj_str,str::from_utf8(&output).unwrap());
+    }
+
+    #[test]
+    fn load_error_context() {
+        let obj_str = "v 0 0 0\n\n# comment\n\nv 1 0 0\r\nf 1 2 7/1\n";
+
+        // Blank lines are counted
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let expected = ErrorContext {
+            line : 6,
+            column : 7,
+            token : String::from("7"),
+            expected : String::from("an index not above 2"),
+            text : String::new(),
+        };
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((expected,IndexKind::Vertex,7),(context,kind,index));
+            },
+            _ => panic!(),
+        };
+
+        let obj_str = "v 0 0 0\n  vt  0.5 x\r\n";
+        let expected = ErrorContext {
+            line : 2,
+            column : 11,
+            token : String::from("x"),
+            expected : String::from("1 to 3 floats for `vt`"),
+            text : String::from("  vt  0.5 x"),
+        };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match ObjData::load(&mut input).err().unwrap() {
+            LoadingError::Parse(context) => assert_eq!(expected,context),
+            _ => panic!(),
+        };
+    }
+
+    #[test]
+    fn display_errors() {
+        let mut input = BufReader::new("v 0 0 0\nf 1 1 -2\n".as_bytes());
+        let err = ObjData::load(&mut input).err().unwrap();
+        let expected = "line 2, column 7: vertex index out of range `-2`, expected a negative index not below -1\n \
+                        2 | f 1 1 -2\n   |       ^^";
+        assert_eq!(expected,err.to_string());
+
+        let mut input = BufReader::new("f 1 2 3\n".as_bytes());
+        let err = ObjData::load(&mut input).err().unwrap();
+        let expected = "line 1, column 7: vertex index out of range `3`, expected an index not above 0";
+        assert_eq!(expected,err.to_string());
+
+        let err = LoadingError::from(io::Error::other("disk failure"));
+        assert_eq!("I/O error: disk failure",err.to_string());
+        assert!(err.context().is_none());
+        assert_eq!("disk failure",error::Error::source(&err).unwrap().to_string());
+    }
+
     #[test]
     fn write_vertices() {
         let mut data = ObjData::new();
-        data.vertices = vec![(1.,-2.,-3.5,1f32),
+        data.vertices = vec![(1.,-2.,-3.5,1. as Float),
         (1.,-1.,1.,1.),
         (-1.,-1.,1.,0.5),
         (-1.,-1.,-1.,1.)];
@@ -704,15 +2232,21 @@ vt 1 0 1
     #[test]
     fn write_faces() {
         let mut data = ObjData::new();
-        data.faces = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
         vec![(7,None,None), (5,None,None), (4,None,None)],
         vec![(3,None,None), (4,None,None), (5,None,None)],
         vec![(7,Some(2),Some(1)), (5,Some(4),Some(2)), (4,Some(6),Some(0))],
         vec![(8,Some(3),None), (6,Some(2),None), (2,Some(1),None)],
-        ];
+        ]);
         let obj = Object {
             name : String::from(""),
-            primitives : vec![0,1,2,3,4]
+            primitives : vec![0,1,2,3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3),Element::Face(4)],
         };
         data.objects = vec![obj];
         let expected =
@@ -731,19 +2265,31 @@ f 9/4/ 7/3/ 3/2/
     #[test]
     fn write_objects() {
         let mut data = ObjData::new();
-        data.faces = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
         vec![(7,None,None), (5,None,None), (4,None,None)],
         vec![(3,None,None), (4,None,None), (5,None,None)],
         vec![(7,Some(2),Some(1)), (5,Some(4),Some(2)), (4,Some(6),Some(0))],
         vec![(8,Some(3),None), (6,Some(2),None), (2,Some(1),None)],
-        ];
+        ]);
         let obj1 = Object {
             name : String::from(""),
-            primitives : vec![0,1]
+            primitives : vec![0,1],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1)],
         };
         let obj2 = Object {
             name : String::from("Test"),
-            primitives : vec![2,3,4]
+            primitives : vec![2,3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(2),Element::Face(3),Element::Face(4)],
         };
         data.objects = vec![obj1,obj2];
         let expected =
@@ -763,28 +2309,49 @@ f 9/4/ 7/3/ 3/2/
     #[test]
     fn write_groups() {
         let mut data = ObjData::new();
-        data.faces = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
         vec![(7,None,None), (5,None,None), (4,None,None)],
         vec![(3,None,None), (4,None,None), (5,None,None)],
         vec![(7,Some(2),Some(1)), (5,Some(4),Some(2)), (4,Some(6),Some(0))],
         vec![(8,Some(3),None), (6,Some(2),None), (2,Some(1),None)],
-        ];
+        ]);
         let obj = Object {
             name : String::from(""),
-            primitives : vec![0,1,2,3,4]
+            primitives : vec![0,1,2,3,4],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3),Element::Face(4)],
         };
         data.objects = vec![obj];
         let gr1 = Group {
             name : String::from("gr1"),
-            indexes : vec!(0,1).into_iter().collect()
+            indexes : vec!(0,1).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         let gr2 = Group {
             name : String::from("gr2"),
-            indexes : vec!(0,1,2).into_iter().collect()
+            indexes : vec!(0,1,2).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         let gr3 = Group {
             name : String::from("gr3"),
-            indexes : vec!(3,4).into_iter().collect()
+            indexes : vec!(3,4).into_iter().collect(),
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
         };
         data.groups = vec![gr1,gr2,gr3];
         let expected =
@@ -802,4 +2369,216 @@ f 9/4/ 7/3/ 3/2/
         let buf = output.into_inner().unwrap();
         assert_eq!(expected,str::from_utf8(&buf).unwrap());
     }
+
+    #[test]
+    fn write_relative_indices() {
+        let mut data = ObjData::new();
+        data.vertices = vec![(0.,0.,0.,1.),
+        (1.,0.,0.,1.),
+        (1.,1.,0.,1.)];
+        data.texcoords = vec![(0.,0.,0.),
+        (1.,1.,0.)];
+        data.faces = faces(vec![ vec![(0,Some(0),None), (1,Some(1),None), (2,Some(1),None)]]);
+        let obj = Object {
+            name : String::from(""),
+            primitives : vec![0],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0)],
+        };
+        data.objects = vec![obj];
+        let expected =
+        r#"v 0 0 0 1
+v 1 0 0 1
+v 1 1 0 1
+vt 0 0 0
+vt 1 1 0
+f -3/-2/ -2/-1/ -1/-1/
+"#;
+        let options = WriteOptions { relative_indices : true };
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write_with_options(&mut output,&options).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn write_smoothing_groups() {
+        let mut data = ObjData::new();
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        vec![(7,None,None), (5,None,None), (4,None,None)],
+        vec![(3,None,None), (4,None,None), (5,None,None)],
+        vec![(8,Some(3),None), (6,Some(2),None), (2,Some(1),None)],
+        ]);
+        let obj = Object {
+            name : String::from(""),
+            primitives : vec![0,1,2,3],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3)],
+        };
+        data.objects = vec![obj];
+        data.smoothing_groups = vec![0,2,2,0];
+        let expected =
+        r#"f 2//1 4//1 1//1
+s 2
+f 8// 6// 5//
+f 4// 5// 6//
+s off
+f 9/4/ 7/3/ 3/2/
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn write_lines_and_points() {
+        let mut data = ObjData::new();
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))]]);
+        data.lines = vec![vec![(0,None),(1,None),(2,None)],
+        vec![(3,Some(0)),(4,Some(1))]];
+        data.points = vec![vec![5,6,8]];
+        let obj1 = Object {
+            name : String::from(""),
+            primitives : vec![0],
+            lines : vec![0],
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : Vec::new(),
+        };
+        let obj2 = Object {
+            name : String::from("Guides"),
+            primitives : Vec::new(),
+            lines : vec![1],
+            points : vec![0],
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Line(1),Element::Point(0)],
+        };
+        data.objects = vec![obj1,obj2];
+        let gr1 = Group {
+            name : String::from("gr1"),
+            indexes : Vec::new(),
+            lines : vec!(0,1).into_iter().collect(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+        };
+        data.groups = vec![gr1];
+        let expected =
+        r#"f 2//1 4//1 1//1
+g gr1
+l 1 2 3
+o Guides
+l 4/1 5/2
+g default
+p 6 7 9
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn write_unknown_statements() {
+        let mut data = ObjData::new();
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))]]);
+        let obj = Object {
+            name : String::from("Test"),
+            primitives : vec![0],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0)],
+        };
+        data.objects = vec![obj];
+        data.groups = vec![Group::new(String::from("gr1"))];
+        data.unknown_statements = vec![UnknownStatement {
+            line : 0,
+            text : String::from("bevel on"),
+            object : None,
+            groups : Vec::new(),
+        },
+        UnknownStatement {
+            line : 3,
+            text : String::from("lod 3"),
+            object : Some(0),
+            groups : vec![0],
+        }];
+        let expected =
+        r#"bevel on
+o Test
+f 2//1 4//1 1//1
+g gr1
+lod 3
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn write_materials() {
+        let mut data = ObjData::new();
+        data.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+        vec![(7,None,None), (5,None,None), (4,None,None)],
+        vec![(3,None,None), (4,None,None), (5,None,None)],
+        vec![(0,None,None), (1,None,None), (2,None,None)],
+        ]);
+        let obj = Object {
+            name : String::from(""),
+            primitives : vec![0,1,2,3],
+            lines : Vec::new(),
+            points : Vec::new(),
+            curves : Vec::new(),
+            curves2 : Vec::new(),
+            surfaces : Vec::new(),
+            elements : vec![Element::Face(0),Element::Face(1),Element::Face(2),Element::Face(3)],
+        };
+        data.objects = vec![obj];
+        data.material_libraries = vec![String::from("cube.mtl")];
+        data.materials = vec![String::from("Red"),String::from("Blue")];
+        data.face_materials = vec![Some(1),Some(1),Some(0),None];
+        let expected =
+        r#"mtllib cube.mtl
+usemtl Blue
+f 2//1 4//1 1//1
+f 8// 6// 5//
+usemtl Red
+f 4// 5// 6//
+usemtl
+f 1// 2// 3//
+"#;
+        let mut output = BufWriter::new(Vec::<u8>::new());
+        assert!(data.write(&mut output).is_ok());
+        let buf = output.into_inner().unwrap();
+        assert_eq!(expected,str::from_utf8(&buf).unwrap());
+    }
+
+    #[test]
+    fn load_write_materials() {
+        // Faces without material are kept between faces with materials
+        let obj_str = with_elements("f 1 2 3\nusemtl Red\nf 1 2 3\nusemtl\nf 1 2 3\nusemtl Blue\nf 1 2 3\n");
+        let data = ObjData::from_str(&obj_str).ok().unwrap();
+        assert_eq!(vec![None,Some(0),None,Some(1)],data.face_materials);
+        let reloaded = ObjData::from_str(&data.to_string()).ok().unwrap();
+        assert_eq!(data.materials,reloaded.materials);
+        assert_eq!(data.face_materials,reloaded.face_materials);
+    }
 }
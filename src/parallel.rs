@@ -0,0 +1,242 @@
+use std::panic;
+use std::thread;
+use obj::{Float,LoadingError,LoadOptions,SourceLine};
+use reader::{Statement,parse_statement};
+use visitor::{ObjVisitor,VisitState};
+use bytes::{FastStatement,from_utf8,parse_fast,fast_argument,visit_bytes};
+
+/// Parse bytes in memory with `options` on `threads` threads, giving each statement to `visitor`
+/// like `visit_bytes`.
+///
+/// The bytes are split into chunks of whole lines parsed in parallel, then the statements
+/// of every chunk are given in order to `visitor` on the calling thread, so that relative indices,
+/// objects, groups and materials are the same as if the file was parsed on a single thread.
+/// Errors are also the same, the first one in the file being returned.
+///
+/// # Examples
+///
+/// ```
+/// use lwobj::{ObjVisitor,FaceVertex,LoadOptions,visit_bytes_parallel};
+///
+/// struct FaceCounter {
+///     faces : usize,
+/// }
+///
+/// impl ObjVisitor for FaceCounter {
+///     fn face(&mut self, _vertices : &[FaceVertex]) {
+///         self.faces += 1;
+///     }
+/// }
+///
+/// let obj = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf -1 -2 -3\n";
+/// let mut counter = FaceCounter { faces : 0 };
+/// assert!(visit_bytes_parallel(obj.as_bytes(),&LoadOptions::default(),4,&mut counter).is_ok());
+/// assert_eq!(2,counter.faces);
+/// ```
+pub fn visit_bytes_parallel<V : ObjVisitor>(bytes : &[u8], options : &LoadOptions, threads : usize, visitor : &mut V)
+    -> Result<(),LoadingError> {
+    if threads <= 1 {
+        return visit_bytes(bytes,options,visitor);
+    }
+    let text = from_utf8(bytes)?;
+    let lenient = options.lenient;
+    let mut state = VisitState::new();
+    thread::scope(|scope| -> Result<(),LoadingError> {
+        let mut handles = Vec::with_capacity(threads);
+        let mut first_line = 1;
+        for chunk in split_lines(text,threads) {
+            handles.push(scope.spawn(move || Chunk::parse(chunk,first_line,lenient)));
+            first_line += chunk.bytes().filter(|&c| c == b'\n').count();
+        }
+        // Chunks are replayed as soon as they are parsed, while the following ones are still parsed
+        for handle in handles {
+            let chunk = handle.join().unwrap_or_else(|err| panic::resume_unwind(err));
+            chunk.replay(&mut state,visitor)?;
+        }
+        Ok(())
+    })?;
+    state.finish()
+}
+
+// Split `text` into about `n` chunks of whole lines.
+fn split_lines(text : &str, n : usize) -> Vec<&str> {
+    let mut chunks = Vec::with_capacity(n);
+    let mut start = 0;
+    for k in 1..n+1 {
+        let mut end = (text.len() * k / n).max(start);
+        match text.as_bytes()[end..].iter().position(|&c| c == b'\n') {
+            Some(i) if k < n => end += i+1,
+            _ => end = text.len(),
+        }
+        if end > start {
+            chunks.push(&text[start..end]);
+            start = end;
+        }
+    }
+    chunks
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum FastKind {
+    Vertex,
+    Normal,
+    TexCoord,
+    Face,
+}
+
+// Statements of a chunk, consecutive statements of a kind read by `parse_fast` being counted together.
+enum Event<'a> {
+    Fast(FastKind,usize),
+    Statement(SourceLine<'a>,Result<Statement,LoadingError>),
+}
+
+// Statements of a chunk of lines parsed by a thread.
+struct Chunk<'a> {
+    events : Vec<Event<'a>>,
+    vertices : Vec<(Float,Float,Float,Float)>,
+    normals : Vec<(Float,Float,Float)>,
+    texcoords : Vec<(Float,Float,Float)>,
+    // `v/vt/vn` references of every face, each face being given by its line and number of references.
+    refs : Vec<(isize,Option<isize>,Option<isize>)>,
+    faces : Vec<(SourceLine<'a>,usize)>,
+}
+
+impl<'a> Chunk<'a> {
+    // Parse the lines of `text`, numbered from `first_line`.
+    fn parse(text : &'a str, first_line : usize, lenient : bool) -> Chunk<'a> {
+        let mut chunk = Chunk {
+            events : Vec::new(),
+            vertices : Vec::new(),
+            normals : Vec::new(),
+            texcoords : Vec::new(),
+            refs : Vec::new(),
+            faces : Vec::new(),
+        };
+        for (i,text) in text.split('\n').enumerate() {
+            let line = SourceLine::new(first_line+i,text);
+            let start = chunk.refs.len();
+            let kind = match parse_fast(line.text,&mut chunk.refs) {
+                Some(FastStatement::Vertex(v)) => {
+                    chunk.vertices.push(v);
+                    FastKind::Vertex
+                },
+                Some(FastStatement::Normal(vn)) => {
+                    chunk.normals.push(vn);
+                    FastKind::Normal
+                },
+                Some(FastStatement::TexCoord(vt)) => {
+                    chunk.texcoords.push(vt);
+                    FastKind::TexCoord
+                },
+                Some(FastStatement::Face) => {
+                    chunk.faces.push((line,chunk.refs.len() - start));
+                    FastKind::Face
+                },
+                None => {
+                    if let Some(statement) = parse_statement(&line,lenient) {
+                        chunk.events.push(Event::Statement(line,statement));
+                    }
+                    continue;
+                },
+            };
+            match chunk.events.last_mut() {
+                Some(&mut Event::Fast(last,ref mut count)) if last == kind => *count += 1,
+                _ => chunk.events.push(Event::Fast(kind,1)),
+            }
+        }
+        chunk
+    }
+
+    // Give the statements to `visitor` in order, stopping at the first error.
+    fn replay<V : ObjVisitor>(self, state : &mut VisitState, visitor : &mut V) -> Result<(),LoadingError> {
+        let mut vertices = self.vertices.into_iter();
+        let mut normals = self.normals.into_iter();
+        let mut texcoords = self.texcoords.into_iter();
+        let mut faces = self.faces.iter();
+        let mut refs = &self.refs[..];
+        for event in self.events {
+            match event {
+                Event::Fast(FastKind::Vertex,count) => for v in vertices.by_ref().take(count) {
+                    state.vertex(visitor,v);
+                },
+                Event::Fast(FastKind::Normal,count) => for vn in normals.by_ref().take(count) {
+                    state.normal(visitor,vn);
+                },
+                Event::Fast(FastKind::TexCoord,count) => for vt in texcoords.by_ref().take(count) {
+                    state.texcoord(visitor,vt);
+                },
+                Event::Fast(FastKind::Face,count) => for &(ref line,len) in faces.by_ref().take(count) {
+                    let (face,rest) = refs.split_at(len);
+                    state.face(visitor,face,line,|k| fast_argument(line.text,k))?;
+                    refs = rest;
+                },
+                Event::Statement(line,statement) => state.apply(visitor,statement?,&line)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use obj::*;
+    use parallel::*;
+
+    #[test]
+    fn split_chunks() {
+        assert_eq!(vec!["a\nb\n","c\n","d"],split_lines("a\nb\nc\nd",3));
+        assert_eq!(vec!["abcdef\n","g"],split_lines("abcdef\ng",4));
+        assert_eq!(Vec::<&str>::new(),split_lines("",4));
+    }
+
+    #[test]
+    fn parse_bytes_parallel() {
+        let cube = fs::read("cube.obj").unwrap();
+        let expected = ObjData::parse_bytes(&cube).ok().unwrap();
+        for threads in 1..8 {
+            let data = ObjData::parse_bytes_parallel(&cube,threads).ok().unwrap();
+            assert_eq!(expected.vertices,data.vertices);
+            assert_eq!(expected.normals,data.normals);
+            assert_eq!(expected.faces,data.faces);
+            assert_eq!(expected.objects,data.objects);
+            assert_eq!(expected.groups,data.groups);
+        }
+
+        // Relative indices, objects, groups and materials spanning chunks
+        let obj_str = "o A\nv 0 0 0\nv 1 0 0\nv 0 1 0\ng gr1\nusemtl Red\nf -3 -2 -1\n# comment\n\
+                       o B\r\nv 0 0 1\r\nf -1 -2 -3\r\ns 1\r\nvt 0.5 0.5\r\nf 4/1 1/1 2/1\r\n\
+                       g gr2\nusemtl Blue\nl 1 4\nf 1 2 3\nusemtl Red\np -1\nbevel on\nf -4 -3 -2";
+        let options = LoadOptions { lenient : true };
+        let expected = ObjData::load_with_options(&mut obj_str.as_bytes(),&options).ok().unwrap();
+        for threads in 1..12 {
+            let data = ObjData::parse_bytes_parallel_with_options(obj_str.as_bytes(),threads,&options).ok().unwrap();
+            assert_eq!(expected.vertices,data.vertices);
+            assert_eq!(expected.texcoords,data.texcoords);
+            assert_eq!(expected.faces,data.faces);
+            assert_eq!(expected.lines,data.lines);
+            assert_eq!(expected.points,data.points);
+            assert_eq!(expected.objects,data.objects);
+            assert_eq!(expected.groups,data.groups);
+            assert_eq!(expected.materials,data.materials);
+            assert_eq!(expected.face_materials,data.face_materials);
+            assert_eq!(expected.smoothing_groups,data.smoothing_groups);
+            assert_eq!(expected.unknown_statements,data.unknown_statements);
+        }
+    }
+
+    #[test]
+    fn parse_bytes_parallel_errors() {
+        let errors = ["v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nv 1 0\nf 1 2 a\n",
+                      "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 -5 2\nv 1\n",
+                      "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 1 2 3\nf 1 2 5\nv 0 0 0\n",
+                      "v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\ncurv 0 1 1 2\nf 1 2 3\n"];
+        for obj_str in errors.iter() {
+            let expected = ObjData::load(&mut obj_str.as_bytes()).err().unwrap();
+            for threads in 2..8 {
+                let error = ObjData::parse_bytes_parallel(obj_str.as_bytes(),threads).err().unwrap();
+                assert_eq!(expected.to_string(),error.to_string());
+            }
+        }
+    }
+}
@@ -0,0 +1,710 @@
+use std::io;
+use std::fmt;
+use obj::{Float,LoadingError,LoadOptions,SourceLine,parse,join_args};
+use freeform::{CurveType,Technique};
+
+/// Parametric direction of a free-form surface given to `bmat` and `parm`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Direction {
+    U,
+    V,
+}
+
+/// A statement of a file, as read by an `ObjReader`.
+///
+/// Indices are kept as written: positive ones start at 1 and negative ones
+/// count backward from the last element.
+#[derive(PartialEq, Debug, Clone)]
+#[allow(clippy::type_complexity)]
+pub enum Statement {
+    /// `v x y z [w]`, `w` being 1 if it isn't written.
+    Vertex((Float,Float,Float,Float)),
+    /// `vn x y z`.
+    Normal((Float,Float,Float)),
+    /// `vt u [v] [w]`, missing values being 0.
+    TexCoord((Float,Float,Float)),
+    /// `vp u [v] [w]`, `v` being 0 and `w` 1 if they aren't written.
+    ParameterVertex((Float,Float,Float)),
+    /// `f v/vt/vn ...`.
+    Face(Vec<(isize,Option<isize>,Option<isize>)>),
+    /// `l v/vt ...`.
+    Line(Vec<(isize,Option<isize>)>),
+    /// `p v ...`.
+    Point(Vec<isize>),
+    /// `o name`.
+    Object(String),
+    /// `g name ...`.
+    Group(Vec<String>),
+    /// `s group`, 0 meaning `s off`.
+    SmoothingGroup(u32),
+    /// `mtllib file ...`.
+    MaterialLibrary(Vec<String>),
+    /// `usemtl name`, the name being empty for `usemtl` alone which selects no material.
+    Material(String),
+    /// `cstype [rat] type`, with whether `rat` is written.
+    CurveType(CurveType,bool),
+    /// `deg u [v]`, `v` being 0 if it isn't written.
+    Degree(usize,usize),
+    /// `step u [v]`, `v` being 0 if it isn't written.
+    Step(usize,usize),
+    /// `bmat u|v values`.
+    BasisMatrix(Direction,Vec<Float>),
+    /// `ctech technique`.
+    CurveTechnique(Technique),
+    /// `stech technique`.
+    SurfaceTechnique(Technique),
+    /// `curv u0 u1 v ...`.
+    Curve((Float,Float),Vec<isize>),
+    /// `curv2 vp ...`.
+    Curve2(Vec<isize>),
+    /// `surf s0 s1 t0 t1 v/vt/vn ...`.
+    Surface((Float,Float),(Float,Float),Vec<(isize,Option<isize>,Option<isize>)>),
+    /// `parm u|v values`.
+    Parameters(Direction,Vec<Float>),
+    /// `trim u0 u1 curv2d ...`.
+    Trim(Vec<(Float,Float,isize)>),
+    /// `hole u0 u1 curv2d ...`.
+    Hole(Vec<(Float,Float,isize)>),
+    /// `scrv u0 u1 curv2d ...`.
+    SpecialCurve(Vec<(Float,Float,isize)>),
+    /// `sp vp ...`.
+    SpecialPoints(Vec<isize>),
+    /// `end`.
+    End,
+    /// Unsupported statement kept verbatim by a lenient reader.
+    Unknown(String),
+}
+
+/// Iterator over the statements of a file.
+///
+/// Comments and empty lines are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use lwobj::{ObjReader,Statement};
+///
+/// let mut reader = ObjReader::new("v 0 0 0\n# comment\nf 1 -1 1\n".as_bytes());
+/// assert_eq!(Statement::Vertex((0.,0.,0.,1.)),reader.next().unwrap().ok().unwrap());
+/// assert_eq!(Statement::Face(vec![(1,None,None),(-1,None,None),(1,None,None)]),
+///            reader.next().unwrap().ok().unwrap());
+/// assert!(reader.next().is_none());
+/// ```
+pub struct ObjReader<R> {
+    input : R,
+    lenient : bool,
+    buf : String,
+    nb : usize,
+}
+
+/// Writer of statements, one per line.
+///
+/// # Examples
+///
+/// ```
+/// use lwobj::{ObjReader,ObjWriter,Statement};
+///
+/// // Remove the normals of a file
+/// let reader = ObjReader::new("v 0 0 0\nvn 0 0 1\nf 1//1 1//1 1//1\n".as_bytes());
+/// let mut writer = ObjWriter::new(Vec::<u8>::new());
+/// for statement in reader {
+///     match statement.ok().unwrap() {
+///         Statement::Normal(_) => {},
+///         Statement::Face(vertices) => {
+///             let vertices = vertices.into_iter().map(|(v,vt,_)| (v,vt,None)).collect();
+///             writer.write(&Statement::Face(vertices)).ok().unwrap();
+///         },
+///         statement => writer.write(&statement).ok().unwrap(),
+///     }
+/// }
+/// assert_eq!(b"v 0 0 0 1\nf 1 1 1\n".to_vec(),writer.into_inner());
+/// ```
+pub struct ObjWriter<W> {
+    output : W,
+}
+
+fn parse_range(args : &[&str], line : &SourceLine) -> Result<(Float,Float), LoadingError> {
+    let values = parse::<Float>(args[..2].to_vec(),line,"a range of 2 floats")?;
+    Ok((values[0],values[1]))
+}
+
+fn parse_index(s : &str, line : &SourceLine) -> Result<isize, LoadingError> {
+    match s.parse::<isize>() {
+        Ok(i) => Ok(i),
+        Err(_) => Err(line.parse_error(s,"an index")),
+    }
+}
+
+fn parse_indices(args : &[&str], line : &SourceLine) -> Result<Vec<isize>, LoadingError> {
+    let mut vec = Vec::new();
+    for arg in args {
+        vec.push(parse_index(arg,line)?);
+    }
+    Ok(vec)
+}
+
+fn parse_optional_index(s : Option<&&str>, line : &SourceLine) -> Result<Option<isize>, LoadingError> {
+    match s {
+        Some(s) if !s.is_empty() => Ok(Some(parse_index(s,line)?)),
+        _ => Ok(None),
+    }
+}
+
+// Parse a `v/vt/vn` reference of a `f` or `surf` statement.
+fn parse_vertex_ref(arg : &str, line : &SourceLine) -> Result<(isize,Option<isize>,Option<isize>), LoadingError> {
+    let index : Vec<_> = arg.split('/').collect();
+    if index.len() > 3 {
+        return Err(line.wrong_arguments(arg,"at most 3 indices in `v/vt/vn`"));
+    }
+    Ok((parse_index(index[0],line)?,parse_optional_index(index.get(1),line)?,parse_optional_index(index.get(2),line)?))
+}
+
+fn parse_usize(s : &str, line : &SourceLine, expected : &str) -> Result<usize, LoadingError> {
+    match s.parse::<usize>() {
+        Ok(v) => Ok(v),
+        Err(_) => Err(line.parse_error(s,expected)),
+    }
+}
+
+// Parse the 1 or 2 integers of `deg` and `step`.
+fn parse_pair(keyword : &str, args : &[&str], line : &SourceLine) -> Result<(usize,usize), LoadingError> {
+    let expected = format!("1 or 2 integers for `{}`",keyword);
+    match args.len() {
+        1 => Ok((parse_usize(args[0],line,&expected)?,0)),
+        2 => Ok((parse_usize(args[0],line,&expected)?,parse_usize(args[1],line,&expected)?)),
+        _ => Err(line.wrong_arguments(keyword,&expected)),
+    }
+}
+
+fn parse_direction(s : &str, line : &SourceLine) -> Result<Direction, LoadingError> {
+    match s {
+        "u" => Ok(Direction::U),
+        "v" => Ok(Direction::V),
+        _ => Err(line.parse_error(s,"`u` or `v`")),
+    }
+}
+
+fn parse_technique(keyword : &str, args : &[&str], surface : bool, line : &SourceLine) -> Result<Technique, LoadingError> {
+    let techniques = if surface {
+        "`cparma`, `cparmb`, `cspace` or `curv`"
+    } else {
+        "`cparm`, `cspace` or `curv`"
+    };
+    if args.is_empty() {
+        return Err(line.wrong_arguments(keyword,&format!("a technique {}",techniques)));
+    }
+    let len = match (args[0],surface) {
+        ("cparm",false) | ("cparmb",true) | ("cspace",_) => 1,
+        ("cparma",true) | ("curv",_) => 2,
+        _ => return Err(line.parse_error(args[0],&format!("a technique {}",techniques))),
+    };
+    let expected = format!("{} floats for `{}`",len,args[0]);
+    let values = parse::<f32>(args[1..].to_vec(),line,&expected)?;
+    if values.len() != len {
+        return Err(line.wrong_arguments(args[0],&expected));
+    }
+    Ok(match args[0] {
+        "cparm" => Technique::Parametric(values[0],values[0]),
+        "cparma" => Technique::Parametric(values[0],values[1]),
+        "cparmb" => Technique::ParametricTrimmed(values[0]),
+        "cspace" => Technique::Spatial(values[0]),
+        _ => Technique::Curvature(values[0],values[1]),
+    })
+}
+
+fn parse_curve_refs(keyword : &str, args : &[&str], line : &SourceLine) -> Result<Vec<(Float,Float,isize)>, LoadingError> {
+    if args.is_empty() || args.len() % 3 != 0 {
+        return Err(line.wrong_arguments(keyword,"groups of `u0 u1 curv2d`"));
+    }
+    let mut refs = Vec::new();
+    for chunk in args.chunks(3) {
+        let range = parse_range(chunk,line)?;
+        refs.push((range.0,range.1,parse_index(chunk[2],line)?));
+    }
+    Ok(refs)
+}
+
+// Parse the statement of `line`, `None` for a comment or an empty line.
+// An unsupported statement is an error unless `lenient` is set.
+pub fn parse_statement(line : &SourceLine, lenient : bool) -> Option<Result<Statement, LoadingError>> {
+    if line.text.starts_with('#') {
+        return None;
+    }
+    let mut iter = line.text.split_whitespace();
+    let keyword = iter.next()?;
+    let args : Vec<_> = iter.collect();
+    Some(parse_arguments(keyword,&args,line,lenient))
+}
+
+fn parse_arguments(keyword : &str, args : &[&str], line : &SourceLine, lenient : bool) -> Result<Statement, LoadingError> {
+    Ok(match keyword {
+        "v" => {
+            let values = parse::<Float>(args.to_vec(),line,"3 or 4 floats for `v`")?;
+            match values.len() {
+                3 => Statement::Vertex((values[0],values[1],values[2],1.0)),
+                4 => Statement::Vertex((values[0],values[1],values[2],values[3])),
+                _ => return Err(line.wrong_arguments(keyword,"3 or 4 floats for `v`")),
+            }
+        },
+        "vn" => {
+            let values = parse::<Float>(args.to_vec(),line,"3 floats for `vn`")?;
+            if values.len() != 3 {
+                return Err(line.wrong_arguments(keyword,"3 floats for `vn`"));
+            }
+            Statement::Normal((values[0],values[1],values[2]))
+        },
+        "vt" => {
+            let values = parse::<Float>(args.to_vec(),line,"1 to 3 floats for `vt`")?;
+            match values.len() {
+                1 => Statement::TexCoord((values[0],0.,0.)),
+                2 => Statement::TexCoord((values[0],values[1],0.)),
+                3 => Statement::TexCoord((values[0],values[1],values[2])),
+                _ => return Err(line.wrong_arguments(keyword,"1 to 3 floats for `vt`")),
+            }
+        },
+        "vp" => {
+            let values = parse::<Float>(args.to_vec(),line,"1 to 3 floats for `vp`")?;
+            match values.len() {
+                1 => Statement::ParameterVertex((values[0],0.,1.)),
+                2 => Statement::ParameterVertex((values[0],values[1],1.)),
+                3 => Statement::ParameterVertex((values[0],values[1],values[2])),
+                _ => return Err(line.wrong_arguments(keyword,"1 to 3 floats for `vp`")),
+            }
+        },
+        "s" => {
+            if args.len() != 1 {
+                return Err(line.wrong_arguments(keyword,"a group number or `off` for `s`"));
+            }
+            match args[0] {
+                "off" => Statement::SmoothingGroup(0),
+                arg => match arg.parse::<u32>() {
+                    Ok(val) => Statement::SmoothingGroup(val),
+                    Err(_) => return Err(line.parse_error(arg,"a group number or `off` for `s`")),
+                },
+            }
+        },
+        "f" => {
+            if args.len() < 3 {
+                return Err(line.wrong_arguments(keyword,"at least 3 vertices for `f`"));
+            }
+            let mut vec = Vec::with_capacity(args.len());
+            for arg in args {
+                vec.push(parse_vertex_ref(arg,line)?);
+            }
+            Statement::Face(vec)
+        },
+        "l" => {
+            if args.len() < 2 {
+                return Err(line.wrong_arguments(keyword,"at least 2 vertices for `l`"));
+            }
+            let mut vec = Vec::with_capacity(args.len());
+            for arg in args {
+                let index : Vec<_> = arg.split('/').collect();
+                if index.len() > 2 {
+                    return Err(line.wrong_arguments(arg,"at most 2 indices in `v/vt`"));
+                }
+                vec.push((parse_index(index[0],line)?,parse_optional_index(index.get(1),line)?));
+            }
+            Statement::Line(vec)
+        },
+        "p" => {
+            if args.is_empty() {
+                return Err(line.wrong_arguments(keyword,"at least 1 vertex for `p`"));
+            }
+            Statement::Point(parse_indices(args,line)?)
+        },
+        "o" => {
+            if args.is_empty() {
+                return Err(line.wrong_arguments(keyword,"a name for `o`"));
+            }
+            Statement::Object(join_args(args))
+        },
+        "g" => Statement::Group(args.iter().map(|arg| String::from(*arg)).collect()),
+        "mtllib" => {
+            if args.is_empty() {
+                return Err(line.wrong_arguments(keyword,"at least 1 file for `mtllib`"));
+            }
+            Statement::MaterialLibrary(args.iter().map(|arg| String::from(*arg)).collect())
+        },
+        "usemtl" => Statement::Material(join_args(args)),
+        "cstype" => {
+            let (rational,name) = match args.len() {
+                1 => (false,args[0]),
+                2 if args[0] == "rat" => (true,args[1]),
+                2 => return Err(line.parse_error(args[0],"`rat` or a curve type")),
+                _ => return Err(line.wrong_arguments(keyword,"an optional `rat` and a curve type")),
+            };
+            match CurveType::from_keyword(name) {
+                Some(t) => Statement::CurveType(t,rational),
+                None => return Err(line.parse_error(name,"`bmatrix`, `bezier`, `bspline`, `cardinal` or `taylor`")),
+            }
+        },
+        "deg" => {
+            let (u,v) = parse_pair(keyword,args,line)?;
+            Statement::Degree(u,v)
+        },
+        "step" => {
+            let (u,v) = parse_pair(keyword,args,line)?;
+            Statement::Step(u,v)
+        },
+        "bmat" => {
+            // The matrix can't be empty, so it can't be reset
+            if args.len() < 2 {
+                return Err(line.wrong_arguments(keyword,"`u` or `v` followed by the matrix"));
+            }
+            let values = parse::<Float>(args[1..].to_vec(),line,"floats for `bmat`")?;
+            Statement::BasisMatrix(parse_direction(args[0],line)?,values)
+        },
+        "ctech" => Statement::CurveTechnique(parse_technique(keyword,args,false,line)?),
+        "stech" => Statement::SurfaceTechnique(parse_technique(keyword,args,true,line)?),
+        "curv" => {
+            if args.len() < 4 {
+                return Err(line.wrong_arguments(keyword,"a range and at least 2 vertices for `curv`"));
+            }
+            Statement::Curve(parse_range(args,line)?,parse_indices(&args[2..],line)?)
+        },
+        "curv2" => {
+            if args.len() < 2 {
+                return Err(line.wrong_arguments(keyword,"at least 2 parameter vertices for `curv2`"));
+            }
+            Statement::Curve2(parse_indices(args,line)?)
+        },
+        "surf" => {
+            if args.len() < 5 {
+                return Err(line.wrong_arguments(keyword,"2 ranges and at least 1 vertex for `surf`"));
+            }
+            let mut control_points = Vec::with_capacity(args.len()-4);
+            for arg in &args[4..] {
+                control_points.push(parse_vertex_ref(arg,line)?);
+            }
+            Statement::Surface(parse_range(args,line)?,parse_range(&args[2..],line)?,control_points)
+        },
+        "parm" => {
+            if args.len() < 3 {
+                return Err(line.wrong_arguments(keyword,"`u` or `v` followed by at least 2 floats"));
+            }
+            let values = parse::<Float>(args[1..].to_vec(),line,"floats for `parm`")?;
+            Statement::Parameters(parse_direction(args[0],line)?,values)
+        },
+        "trim" => Statement::Trim(parse_curve_refs(keyword,args,line)?),
+        "hole" => Statement::Hole(parse_curve_refs(keyword,args,line)?),
+        "scrv" => Statement::SpecialCurve(parse_curve_refs(keyword,args,line)?),
+        "sp" => {
+            if args.is_empty() {
+                return Err(line.wrong_arguments(keyword,"at least 1 parameter vertex for `sp`"));
+            }
+            Statement::SpecialPoints(parse_indices(args,line)?)
+        },
+        "end" => {
+            if !args.is_empty() {
+                return Err(line.wrong_arguments(keyword,"no argument for `end`"));
+            }
+            Statement::End
+        },
+        _ if lenient => Statement::Unknown(String::from(line.text.trim())),
+        _ => return Err(line.invalid(keyword,"a supported statement")),
+    })
+}
+
+impl<R : io::BufRead> ObjReader<R> {
+    /// Create a reader of the statements of `input`.
+    pub fn new(input : R) -> ObjReader<R> {
+        ObjReader::with_options(input,&LoadOptions::default())
+    }
+
+    /// Create a reader of the statements of `input` with `options`.
+    ///
+    /// A lenient reader gives unsupported statements as `Statement::Unknown`.
+    pub fn with_options(input : R, options : &LoadOptions) -> ObjReader<R> {
+        ObjReader {
+            input,
+            lenient : options.lenient,
+            buf : String::new(),
+            nb : 0,
+        }
+    }
+
+    /// Line of the last statement read, starting at 1.
+    pub fn line(&self) -> usize {
+        self.nb
+    }
+
+    // The last line read.
+    pub fn source_line(&self) -> SourceLine<'_> {
+        SourceLine::new(self.nb,&self.buf)
+    }
+}
+
+impl<R : io::BufRead> Iterator for ObjReader<R> {
+    type Item = Result<Statement, LoadingError>;
+
+    fn next(&mut self) -> Option<Result<Statement, LoadingError>> {
+        loop {
+            self.buf.clear();
+            match self.input.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(err) => return Some(Err(LoadingError::Io(err))),
+            }
+            self.nb += 1;
+            let statement = parse_statement(&SourceLine::new(self.nb,&self.buf),self.lenient);
+            if statement.is_some() {
+                return statement;
+            }
+        }
+    }
+}
+
+impl<W : io::Write> ObjWriter<W> {
+    pub fn new(output : W) -> ObjWriter<W> {
+        ObjWriter {
+            output,
+        }
+    }
+
+    /// Write `statement` followed by a new line.
+    pub fn write(&mut self, statement : &Statement) -> Result<(), LoadingError> {
+        writeln!(self.output,"{}",statement)?;
+        Ok(())
+    }
+
+    /// Get back the output.
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+fn write_index_ref(f : &mut fmt::Formatter, v : isize, vt : Option<isize>, vn : Option<isize>) -> fmt::Result {
+    write!(f," {}",v)?;
+    match (vt,vn) {
+        (Some(vt),Some(vn)) => write!(f,"/{}/{}",vt,vn),
+        (Some(vt),None) => write!(f,"/{}",vt),
+        (None,Some(vn)) => write!(f,"//{}",vn),
+        (None,None) => Ok(()),
+    }
+}
+
+fn write_list<T : fmt::Display>(f : &mut fmt::Formatter, values : &[T]) -> fmt::Result {
+    for value in values {
+        write!(f," {}",value)?;
+    }
+    Ok(())
+}
+
+fn write_curve_refs(f : &mut fmt::Formatter, keyword : &str, refs : &[(Float,Float,isize)]) -> fmt::Result {
+    f.write_str(keyword)?;
+    for &(u0,u1,curve) in refs {
+        write!(f," {} {} {}",u0,u1,curve)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Direction::U => "u",
+            Direction::V => "v",
+        })
+    }
+}
+
+impl fmt::Display for Statement {
+    /// Write the statement as a line without its end of line.
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Statement::Vertex((x,y,z,w)) => write!(f,"v {} {} {} {}",x,y,z,w),
+            Statement::Normal((x,y,z)) => write!(f,"vn {} {} {}",x,y,z),
+            Statement::TexCoord((u,v,w)) => write!(f,"vt {} {} {}",u,v,w),
+            Statement::ParameterVertex((u,v,w)) => write!(f,"vp {} {} {}",u,v,w),
+            Statement::Face(ref vertices) => {
+                f.write_str("f")?;
+                for &(v,vt,vn) in vertices {
+                    write_index_ref(f,v,vt,vn)?;
+                }
+                Ok(())
+            },
+            Statement::Line(ref vertices) => {
+                f.write_str("l")?;
+                for &(v,vt) in vertices {
+                    write_index_ref(f,v,vt,None)?;
+                }
+                Ok(())
+            },
+            Statement::Point(ref vertices) => {
+                f.write_str("p")?;
+                write_list(f,vertices)
+            },
+            Statement::Object(ref name) => write!(f,"o {}",name),
+            Statement::Group(ref names) => {
+                f.write_str("g")?;
+                write_list(f,names)
+            },
+            Statement::SmoothingGroup(0) => f.write_str("s off"),
+            Statement::SmoothingGroup(group) => write!(f,"s {}",group),
+            Statement::MaterialLibrary(ref files) => {
+                f.write_str("mtllib")?;
+                write_list(f,files)
+            },
+            Statement::Material(ref name) if name.is_empty() => f.write_str("usemtl"),
+            Statement::Material(ref name) => write!(f,"usemtl {}",name),
+            Statement::CurveType(curve_type,rational) => {
+                let rat = if rational {"rat "} else {""};
+                write!(f,"cstype {}{}",rat,curve_type.keyword())
+            },
+            Statement::Degree(u,0) => write!(f,"deg {}",u),
+            Statement::Degree(u,v) => write!(f,"deg {} {}",u,v),
+            Statement::Step(u,0) => write!(f,"step {}",u),
+            Statement::Step(u,v) => write!(f,"step {} {}",u,v),
+            Statement::BasisMatrix(direction,ref values) => {
+                write!(f,"bmat {}",direction)?;
+                write_list(f,values)
+            },
+            Statement::CurveTechnique(technique) => match technique {
+                Technique::Parametric(res,_) | Technique::ParametricTrimmed(res) => write!(f,"ctech cparm {}",res),
+                Technique::Spatial(length) => write!(f,"ctech cspace {}",length),
+                Technique::Curvature(dist,angle) => write!(f,"ctech curv {} {}",dist,angle),
+            },
+            Statement::SurfaceTechnique(technique) => match technique {
+                Technique::Parametric(ures,vres) => write!(f,"stech cparma {} {}",ures,vres),
+                Technique::ParametricTrimmed(res) => write!(f,"stech cparmb {}",res),
+                Technique::Spatial(length) => write!(f,"stech cspace {}",length),
+                Technique::Curvature(dist,angle) => write!(f,"stech curv {} {}",dist,angle),
+            },
+            Statement::Curve((u0,u1),ref vertices) => {
+                write!(f,"curv {} {}",u0,u1)?;
+                write_list(f,vertices)
+            },
+            Statement::Curve2(ref vertices) => {
+                f.write_str("curv2")?;
+                write_list(f,vertices)
+            },
+            Statement::Surface((s0,s1),(t0,t1),ref vertices) => {
+                write!(f,"surf {} {} {} {}",s0,s1,t0,t1)?;
+                for &(v,vt,vn) in vertices {
+                    write_index_ref(f,v,vt,vn)?;
+                }
+                Ok(())
+            },
+            Statement::Parameters(direction,ref values) => {
+                write!(f,"parm {}",direction)?;
+                write_list(f,values)
+            },
+            Statement::Trim(ref refs) => write_curve_refs(f,"trim",refs),
+            Statement::Hole(ref refs) => write_curve_refs(f,"hole",refs),
+            Statement::SpecialCurve(ref refs) => write_curve_refs(f,"scrv",refs),
+            Statement::SpecialPoints(ref vertices) => {
+                f.write_str("sp")?;
+                write_list(f,vertices)
+            },
+            Statement::End => f.write_str("end"),
+            Statement::Unknown(ref text) => f.write_str(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use freeform::*;
+    use reader::*;
+
+    static STATEMENTS : &str =
+r#"mtllib a.mtl b.mtl
+o Test object
+g gr1 gr2
+g
+usemtl Red
+usemtl
+s 2
+s off
+v 1 2 3 1
+vn 0 0 1
+vt 0.5 0.5 0
+vp 0.2 0.2 1
+f 1 -1/2 3//4 5/6/7
+l 1 2/3
+p 1 -2
+cstype rat bspline
+deg 2 1
+step 1
+bmat u 1 0 0 1
+ctech cparm 2
+stech cparma 2 3
+curv 0 1 1 2
+curv2 1 -1
+surf 0 1 0 1 1 2/1 3//1
+parm v 0 0.5 1
+trim 0 1 1 0 1 -1
+hole 0 1 1
+scrv 0 1 2
+sp 1 2
+end
+bevel on
+"#;
+
+    #[test]
+    fn read_statements() {
+        let expected = vec![Statement::MaterialLibrary(vec![String::from("a.mtl"),String::from("b.mtl")]),
+        Statement::Object(String::from("Test object")),
+        Statement::Group(vec![String::from("gr1"),String::from("gr2")]),
+        Statement::Group(Vec::new()),
+        Statement::Material(String::from("Red")),
+        Statement::Material(String::new()),
+        Statement::SmoothingGroup(2),
+        Statement::SmoothingGroup(0),
+        Statement::Vertex((1.,2.,3.,1.)),
+        Statement::Normal((0.,0.,1.)),
+        Statement::TexCoord((0.5,0.5,0.)),
+        Statement::ParameterVertex((0.2,0.2,1.)),
+        Statement::Face(vec![(1,None,None),(-1,Some(2),None),(3,None,Some(4)),(5,Some(6),Some(7))]),
+        Statement::Line(vec![(1,None),(2,Some(3))]),
+        Statement::Point(vec![1,-2]),
+        Statement::CurveType(CurveType::BSpline,true),
+        Statement::Degree(2,1),
+        Statement::Step(1,0),
+        Statement::BasisMatrix(Direction::U,vec![1.,0.,0.,1.]),
+        Statement::CurveTechnique(Technique::Parametric(2.,2.)),
+        Statement::SurfaceTechnique(Technique::Parametric(2.,3.)),
+        Statement::Curve((0.,1.),vec![1,2]),
+        Statement::Curve2(vec![1,-1]),
+        Statement::Surface((0.,1.),(0.,1.),vec![(1,None,None),(2,Some(1),None),(3,None,Some(1))]),
+        Statement::Parameters(Direction::V,vec![0.,0.5,1.]),
+        Statement::Trim(vec![(0.,1.,1),(0.,1.,-1)]),
+        Statement::Hole(vec![(0.,1.,1)]),
+        Statement::SpecialCurve(vec![(0.,1.,2)]),
+        Statement::SpecialPoints(vec![1,2]),
+        Statement::End,
+        Statement::Unknown(String::from("bevel on"))];
+
+        let options = LoadOptions { lenient : true };
+        let reader = ObjReader::with_options(STATEMENTS.as_bytes(),&options);
+        let statements : Vec<Statement> = reader.map(|s| s.ok().unwrap()).collect();
+        assert_eq!(expected,statements);
+
+        // Every statement is read back from its written form
+        let mut writer = ObjWriter::new(Vec::<u8>::new());
+        for statement in &statements {
+            assert!(writer.write(statement).is_ok());
+        }
+        let output = writer.into_inner();
+        let reader = ObjReader::with_options(&output[..],&options);
+        let reread : Vec<Statement> = reader.map(|s| s.ok().unwrap()).collect();
+        assert_eq!(statements,reread);
+    }
+
+    #[test]
+    fn read_errors() {
+        let mut reader = ObjReader::new("v 0 0 0\n\nbevel on\nf 1 a 2\n".as_bytes());
+        assert!(reader.next().unwrap().is_ok());
+        match reader.next().unwrap().err().unwrap() {
+            LoadingError::InvalidLine(context) => assert_eq!((3,"bevel"),(context.line,context.token.as_str())),
+            _ => panic!(),
+        };
+        match reader.next().unwrap().err().unwrap() {
+            LoadingError::Parse(context) => assert_eq!((4,5),(context.line,context.column)),
+            _ => panic!(),
+        };
+        assert_eq!(4,reader.line());
+        assert!(reader.next().is_none());
+    }
+}
@@ -0,0 +1,238 @@
+// Face areas are summed in f64, the type of positions with the `f64` feature.
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+
+use obj::{Float,ObjData};
+
+/// Axis-aligned bounding box.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Bounds {
+    /// Smallest coordinates `(x,y,z)`.
+    pub min : (Float,Float,Float),
+    /// Largest coordinates `(x,y,z)`.
+    pub max : (Float,Float,Float),
+}
+
+/// Counts and measures of an `ObjData` given by `ObjData::stats`.
+#[derive(PartialEq, Debug, Clone)]
+pub struct MeshStats {
+    /// Number of vertices.
+    pub vertices : usize,
+    /// Number of normals.
+    pub normals : usize,
+    /// Number of texture coordinates.
+    pub texcoords : usize,
+    /// Number of faces.
+    pub faces : usize,
+    /// Number of faces of 3 vertices.
+    pub triangles : usize,
+    /// Number of faces of 4 vertices.
+    pub quads : usize,
+    /// Number of faces of more than 4 vertices.
+    pub polygons : usize,
+    /// Number of vertices used by no face, line, point, curve or surface.
+    pub unused_vertices : usize,
+    /// Area of the faces.
+    pub area : f64,
+    /// Center of the faces, weighted by their area. `None` if they have no area.
+    pub centroid : Option<(f64,f64,f64)>,
+}
+
+impl Bounds {
+    /// Box containing only `(x,y,z)`.
+    pub fn new((x,y,z) : (Float,Float,Float)) -> Bounds {
+        Bounds {
+            min : (x,y,z),
+            max : (x,y,z),
+        }
+    }
+
+    /// Grow the box to contain `(x,y,z)`.
+    pub fn add(&mut self, (x,y,z) : (Float,Float,Float)) {
+        self.min = (self.min.0.min(x),self.min.1.min(y),self.min.2.min(z));
+        self.max = (self.max.0.max(x),self.max.1.max(y),self.max.2.max(z));
+    }
+
+    /// Size of the box along every axis.
+    pub fn size(&self) -> (Float,Float,Float) {
+        (self.max.0-self.min.0,self.max.1-self.min.1,self.max.2-self.min.2)
+    }
+
+    /// Center of the box.
+    pub fn center(&self) -> (Float,Float,Float) {
+        ((self.min.0+self.max.0)/2.,(self.min.1+self.max.1)/2.,(self.min.2+self.max.2)/2.)
+    }
+}
+
+impl ObjData {
+    // Bounds of the vertices `indices` of `vertices`.
+    fn bounds_of<I : IntoIterator<Item=usize>>(&self, indices : I) -> Option<Bounds> {
+        let mut bounds : Option<Bounds> = None;
+        for i in indices {
+            let (x,y,z,_) = self.vertices[i];
+            match bounds {
+                Some(ref mut bounds) => bounds.add((x,y,z)),
+                None => bounds = Some(Bounds::new((x,y,z))),
+            }
+        }
+        bounds
+    }
+
+    // Vertices of the faces, lines and points given by their indices.
+    fn element_vertices<'a>(&'a self, faces : &'a [usize], lines : &'a [usize], points : &'a [usize])
+        -> impl Iterator<Item=usize> + 'a {
+        let faces = faces.iter().flat_map(move |&f| self.faces[f].iter().map(|vertex| vertex.position.0));
+        let lines = lines.iter().flat_map(move |&l| self.lines[l].iter().map(|&(v,_)| v));
+        let points = points.iter().flat_map(move |&p| self.points[p].iter().cloned());
+        faces.chain(lines).chain(points)
+    }
+
+    /// Bounding box of every vertex, `None` if there are no vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\nv 1 -2 0\nv 0 1 3\no A\nf 1 2 3\no B\np 3\n").ok().unwrap();
+    /// let bounds = data.bounds().unwrap();
+    /// assert_eq!((0.,-2.,0.),bounds.min);
+    /// assert_eq!((1.,1.,3.),bounds.max);
+    /// assert_eq!((0.,1.,3.),data.object_bounds(1).unwrap().max);
+    /// ```
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds_of(0..self.vertices.len())
+    }
+
+    /// Bounding box of the vertices of the faces, lines and points of the object `i`,
+    /// `None` if they have no vertices or if there is no object `i`.
+    pub fn object_bounds(&self, i : usize) -> Option<Bounds> {
+        let object = self.objects.get(i)?;
+        self.bounds_of(self.element_vertices(&object.primitives,&object.lines,&object.points))
+    }
+
+    /// Bounding box of the vertices of the faces, lines and points of the group `i`,
+    /// `None` if they have no vertices or if there is no group `i`.
+    pub fn group_bounds(&self, i : usize) -> Option<Bounds> {
+        let group = self.groups.get(i)?;
+        self.bounds_of(self.element_vertices(&group.indexes,&group.lines,&group.points))
+    }
+
+    /// Counts of elements and measures of the faces.
+    ///
+    /// The area and centroid of faces are those of the triangles given by `triangulate_face`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nv 5 5 5\nf 1 2 3 4\n").ok().unwrap();
+    /// let stats = data.stats();
+    /// assert_eq!(1,stats.quads);
+    /// assert_eq!(1,stats.unused_vertices);
+    /// assert_eq!(4.,stats.area);
+    /// assert_eq!(Some((1.,1.,0.)),stats.centroid);
+    /// ```
+    pub fn stats(&self) -> MeshStats {
+        let mut used = vec![false; self.vertices.len()];
+        for v in self.faces.vertices().iter().map(|vertex| vertex.position.0)
+            .chain(self.lines.iter().flat_map(|line| line.iter().map(|&(v,_)| v)))
+            .chain(self.points.iter().flat_map(|points| points.iter().cloned()))
+            .chain(self.curves.iter().flat_map(|curve| curve.control_points.iter().cloned()))
+            .chain(self.surfaces.iter().flat_map(|surface| surface.control_points.iter().map(|&(v,_,_)| v))) {
+            if let Some(used) = used.get_mut(v) {
+                *used = true;
+            }
+        }
+
+        let mut stats = MeshStats {
+            vertices : self.vertices.len(),
+            normals : self.normals.len(),
+            texcoords : self.texcoords.len(),
+            faces : self.faces.len(),
+            triangles : 0,
+            quads : 0,
+            polygons : 0,
+            unused_vertices : used.iter().filter(|&&used| !used).count(),
+            area : 0.,
+            centroid : None,
+        };
+        let mut center = [0.;3];
+        for face in &self.faces {
+            match face.len() {
+                3 => stats.triangles += 1,
+                4 => stats.quads += 1,
+                n if n > 4 => stats.polygons += 1,
+                _ => (),
+            }
+            let points : Vec<[f64;3]> = self.face_positions(face)
+                .map(|&(x,y,z,_)| [x as f64,y as f64,z as f64]).collect();
+            for t in self.triangulate_face(face) {
+                let (a,b,c) = (points[t[0]],points[t[1]],points[t[2]]);
+                let (u,v) = ([b[0]-a[0],b[1]-a[1],b[2]-a[2]],[c[0]-a[0],c[1]-a[1],c[2]-a[2]]);
+                let n = [u[1]*v[2]-u[2]*v[1],u[2]*v[0]-u[0]*v[2],u[0]*v[1]-u[1]*v[0]];
+                let area = (n[0]*n[0]+n[1]*n[1]+n[2]*n[2]).sqrt() / 2.;
+                stats.area += area;
+                for i in 0..3 {
+                    center[i] += area * (a[i]+b[i]+c[i]) / 3.;
+                }
+            }
+        }
+        if stats.area > 0. {
+            stats.centroid = Some((center[0]/stats.area,center[1]/stats.area,center[2]/stats.area));
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use stats::*;
+
+    #[test]
+    fn bounds() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv -1 -1 -1\nv 4 4 4\nv 2 3 1\n\
+                       o A\ng gr1\nf 1 2 3\nl 4 1\no B\ng gr2\np 6\no C\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        assert_eq!(Some(Bounds { min : (-1.,-1.,-1.), max : (4.,4.,4.) }),data.bounds());
+        assert_eq!(Some(Bounds { min : (-1.,-1.,-1.), max : (1.,1.,0.) }),data.object_bounds(0));
+        assert_eq!(Some(Bounds { min : (2.,3.,1.), max : (2.,3.,1.) }),data.group_bounds(1));
+        assert_eq!(None,data.object_bounds(2));
+        assert_eq!(None,data.object_bounds(99));
+        assert_eq!(None,data.group_bounds(99));
+        assert_eq!(None,ObjData::new().bounds());
+
+        let bounds = data.bounds().unwrap();
+        assert_eq!((5.,5.,5.),bounds.size());
+        assert_eq!((1.5,1.5,1.5),bounds.center());
+    }
+
+    #[test]
+    fn stats() {
+        // Unit cube without its bottom face, split in a triangle and a pentagon on one side
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 0 0 1\nv 1 0 1\nv 1 1 1\nv 0 1 1\nv 0.5 0 1\nv 9 9 9\nv 0.5 0 0\n\
+                       vn 0 0 1\nvt 0 0\nvt 1 1\n\
+                       f 5 6 7 8\nf 1 11 2 9 5\nf 2 6 9\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let stats = data.stats();
+        assert_eq!(11,stats.vertices);
+        assert_eq!(1,stats.normals);
+        assert_eq!(2,stats.texcoords);
+        assert_eq!(6,stats.faces);
+        assert_eq!(1,stats.triangles);
+        assert_eq!(4,stats.quads);
+        assert_eq!(1,stats.polygons);
+        assert_eq!(1,stats.unused_vertices);
+        assert!((stats.area - 5.).abs() < 1e-9);
+        let (x,y,z) = stats.centroid.unwrap();
+        assert!((x - 0.5).abs() < 1e-9 && (y - 0.5).abs() < 1e-9 && (z - 0.6).abs() < 1e-9);
+
+        let data = ObjData::from_str("v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 2 0\nv 0 2 0\nl 1 2\nf 1 2 3 4 5 6\n").ok().unwrap();
+        let stats = data.stats();
+        assert_eq!(1,stats.polygons);
+        assert_eq!(0,stats.unused_vertices);
+        assert_eq!(3.,stats.area);
+        assert_eq!(ObjData::new().stats().centroid,None);
+    }
+}
@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+use obj::{Float,ObjData,float_key};
+use face::Face;
+
+/// Errors of tangent computation.
+#[derive(PartialEq, Debug)]
+pub enum TangentError {
+    /// A vertex of the face has no normal.
+    MissingNormal(usize),
+    /// A vertex of the face has no texture coordinates.
+    MissingTexCoord(usize),
+}
+
+/// Tangent frame of a vertex of a face computed by `ObjData::compute_tangents`.
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub struct Tangent {
+    /// Unit tangent in the direction of increasing u, orthogonal to the normal.
+    pub tangent : (Float,Float,Float),
+    /// Bitangent in the direction of increasing v, equal to `sign * normal × tangent`.
+    pub bitangent : (Float,Float,Float),
+    /// 1 or -1, -1 if the texture is mirrored.
+    pub sign : Float,
+}
+
+type Vector = [Float;3];
+
+fn add(a : Vector, b : Vector) -> Vector {
+    [a[0]+b[0],a[1]+b[1],a[2]+b[2]]
+}
+
+fn sub(a : Vector, b : Vector) -> Vector {
+    [a[0]-b[0],a[1]-b[1],a[2]-b[2]]
+}
+
+fn scale(s : Float, a : Vector) -> Vector {
+    [s*a[0],s*a[1],s*a[2]]
+}
+
+fn dot(a : Vector, b : Vector) -> Float {
+    a[0]*b[0] + a[1]*b[1] + a[2]*b[2]
+}
+
+fn cross(a : Vector, b : Vector) -> Vector {
+    [a[1]*b[2]-a[2]*b[1],a[2]*b[0]-a[0]*b[2],a[0]*b[1]-a[1]*b[0]]
+}
+
+fn length(a : Vector) -> Float {
+    dot(a,a).sqrt()
+}
+
+fn not_zero(x : Float) -> bool {
+    x.abs() > Float::MIN_POSITIVE
+}
+
+fn vector_not_zero(a : Vector) -> bool {
+    not_zero(a[0]) || not_zero(a[1]) || not_zero(a[2])
+}
+
+fn normalize(a : Vector) -> Vector {
+    scale(1. / length(a),a)
+}
+
+// `a` projected on the plane orthogonal to the unit vector `n` and normalized if it isn't null.
+fn project(n : Vector, a : Vector) -> Vector {
+    let a = sub(a,scale(dot(n,a),n));
+    if vector_not_zero(a) { normalize(a) } else { a }
+}
+
+// Vertex of a face, vertices with the same position, normal and texture coordinates being the same.
+struct Vertex {
+    position : Vector,
+    normal : Vector,
+    texcoord : [Float;2],
+}
+
+// Tangent space in the direction of increasing u and v.
+#[derive(Clone, Copy)]
+struct TangentSpace {
+    os : Vector,
+    ot : Vector,
+    mag_s : Float,
+    mag_t : Float,
+    // Number of triangles of the face which gave this tangent space to the vertex
+    counter : u8,
+    orient : bool,
+}
+
+impl TangentSpace {
+    fn new() -> TangentSpace {
+        TangentSpace {
+            os : [1.,0.,0.],
+            ot : [0.,1.,0.],
+            mag_s : 1.,
+            mag_t : 1.,
+            counter : 0,
+            orient : false,
+        }
+    }
+
+    // Average of two tangent spaces of a vertex of a quad.
+    fn average(&self, other : &TangentSpace) -> TangentSpace {
+        // Averaging equal spaces would change them slightly
+        if self.mag_s == other.mag_s && self.mag_t == other.mag_t && self.os == other.os && self.ot == other.ot {
+            return *self;
+        }
+        let (os,ot) = (add(self.os,other.os),add(self.ot,other.ot));
+        TangentSpace {
+            os : if vector_not_zero(os) { normalize(os) } else { os },
+            ot : if vector_not_zero(ot) { normalize(ot) } else { ot },
+            mag_s : 0.5 * (self.mag_s + other.mag_s),
+            mag_t : 0.5 * (self.mag_t + other.mag_t),
+            ..*self
+        }
+    }
+}
+
+// Triangle of a face.
+struct Triangle {
+    face : usize,
+    // Vertices and their numbers in the face
+    vertices : [usize;3],
+    corners : [usize;3],
+    os : Vector,
+    ot : Vector,
+    mag_s : Float,
+    mag_t : Float,
+    orient : bool,
+    // Triangle without texture area, which takes the tangent space of its neighbours
+    group_with_any : bool,
+    // Triangle of a quad whose other triangle is degenerate
+    quad_one_degenerate : bool,
+    // Triangles sharing the edge starting at each vertex, and group of each vertex
+    neighbors : [Option<usize>;3],
+    groups : [Option<usize>;3],
+}
+
+impl Triangle {
+    fn new(face : usize, vertices : [usize;3], corners : [usize;3]) -> Triangle {
+        Triangle {
+            face,
+            vertices,
+            corners,
+            os : [0.;3],
+            ot : [0.;3],
+            mag_s : 0.,
+            mag_t : 0.,
+            orient : false,
+            group_with_any : true,
+            quad_one_degenerate : false,
+            neighbors : [None;3],
+            groups : [None;3],
+        }
+    }
+
+    fn corner_of(&self, vertex : usize) -> usize {
+        self.vertices.iter().position(|&v| v == vertex).unwrap()
+    }
+
+    // Compute the tangent space of the triangle.
+    fn init(&mut self, vertices : &[Vertex]) {
+        let (v1,v2,v3) = (&vertices[self.vertices[0]],&vertices[self.vertices[1]],&vertices[self.vertices[2]]);
+        let (t21x,t21y) = (v2.texcoord[0] - v1.texcoord[0],v2.texcoord[1] - v1.texcoord[1]);
+        let (t31x,t31y) = (v3.texcoord[0] - v1.texcoord[0],v3.texcoord[1] - v1.texcoord[1]);
+        let d1 = sub(v2.position,v1.position);
+        let d2 = sub(v3.position,v1.position);
+        let area = t21x*t31y - t21y*t31x;
+        let os = sub(scale(t31y,d1),scale(t21y,d2));
+        let ot = add(scale(-t31x,d1),scale(t21x,d2));
+        self.orient = area > 0.;
+        if not_zero(area) {
+            let (length_os,length_ot) = (length(os),length(ot));
+            let sign = if self.orient { 1. } else { -1. };
+            if not_zero(length_os) {
+                self.os = scale(sign / length_os,os);
+            }
+            if not_zero(length_ot) {
+                self.ot = scale(sign / length_ot,ot);
+            }
+            self.mag_s = length_os / area.abs();
+            self.mag_t = length_ot / area.abs();
+            if not_zero(self.mag_s) && not_zero(self.mag_t) {
+                self.group_with_any = false;
+            }
+        }
+    }
+}
+
+// Triangles around a vertex having the same orientation of texture coordinates, and connected
+// by edges or through triangles without texture area.
+struct Group {
+    vertex : usize,
+    orient : bool,
+    triangles : Vec<usize>,
+}
+
+// Twice the area of a triangle in texture space.
+fn texture_area(vertices : &[Vertex], triangle : &[usize;3]) -> Float {
+    let (t1,t2,t3) = (vertices[triangle[0]].texcoord,vertices[triangle[1]].texcoord,vertices[triangle[2]].texcoord);
+    ((t2[0]-t1[0])*(t3[1]-t1[1]) - (t2[1]-t1[1])*(t3[0]-t1[0])).abs()
+}
+
+// Find the triangles sharing each edge with the opposite direction.
+fn build_neighbors(triangles : &mut [Triangle]) {
+    let mut edges : Vec<(usize,usize,usize,usize)> = Vec::with_capacity(3*triangles.len());
+    for (t,triangle) in triangles.iter().enumerate() {
+        for i in 0..3 {
+            let (a,b) = (triangle.vertices[i],triangle.vertices[(i+1)%3]);
+            edges.push((a.min(b),a.max(b),t,i));
+        }
+    }
+    edges.sort();
+    for e in 0..edges.len() {
+        let (low,high,t,i) = edges[e];
+        if triangles[t].neighbors[i].is_some() {
+            continue;
+        }
+        let (a,b) = (triangles[t].vertices[i],triangles[t].vertices[(i+1)%3]);
+        let other = edges[e+1..].iter().take_while(|edge| edge.0 == low && edge.1 == high).find(|&&(_,_,u,j)| {
+            triangles[u].neighbors[j].is_none() && triangles[u].vertices[j] == b && triangles[u].vertices[(j+1)%3] == a
+        });
+        if let Some(&(_,_,u,j)) = other {
+            triangles[t].neighbors[i] = Some(u);
+            triangles[u].neighbors[j] = Some(t);
+        }
+    }
+}
+
+// Add to the group `g` the triangles around its vertex connected to `t`.
+fn assign_group(triangles : &mut [Triangle], groups : &mut [Group], g : usize, t : usize) {
+    let mut stack = vec![t];
+    while let Some(t) = stack.pop() {
+        let triangle = &mut triangles[t];
+        let i = triangle.corner_of(groups[g].vertex);
+        if triangle.groups[i].is_some() {
+            continue;
+        }
+        // The first group reaching a triangle without texture area gives its orientation
+        if triangle.group_with_any && triangle.groups.iter().all(|g| g.is_none()) {
+            triangle.orient = groups[g].orient;
+        }
+        if triangle.orient != groups[g].orient {
+            continue;
+        }
+        groups[g].triangles.push(t);
+        triangle.groups[i] = Some(g);
+        stack.extend(triangle.neighbors[(i+2)%3]);
+        stack.extend(triangle.neighbors[i]);
+    }
+}
+
+// Tangent space of a vertex, weighting the tangent spaces of the triangles by their angle at the vertex.
+fn eval_tangent_space(triangles : &[Triangle], members : &[usize], vertices : &[Vertex], vertex : usize) -> TangentSpace {
+    let mut result = TangentSpace::new();
+    result.os = [0.;3];
+    result.ot = [0.;3];
+    result.mag_s = 0.;
+    result.mag_t = 0.;
+    let mut angle_sum = 0.;
+    for triangle in members.iter().map(|&t| &triangles[t]).filter(|t| !t.group_with_any) {
+        let i = triangle.corner_of(vertex);
+        let n = vertices[vertex].normal;
+        let os = project(n,triangle.os);
+        let ot = project(n,triangle.ot);
+        let p0 = vertices[triangle.vertices[(i+2)%3]].position;
+        let p1 = vertices[triangle.vertices[i]].position;
+        let p2 = vertices[triangle.vertices[(i+1)%3]].position;
+        let v1 = project(n,sub(p0,p1));
+        let v2 = project(n,sub(p2,p1));
+        let angle = dot(v1,v2).clamp(-1.,1.).acos();
+        result.os = add(result.os,scale(angle,os));
+        result.ot = add(result.ot,scale(angle,ot));
+        result.mag_s += angle * triangle.mag_s;
+        result.mag_t += angle * triangle.mag_t;
+        angle_sum += angle;
+    }
+    if vector_not_zero(result.os) {
+        result.os = normalize(result.os);
+    }
+    if vector_not_zero(result.ot) {
+        result.ot = normalize(result.ot);
+    }
+    if angle_sum > 0. {
+        result.mag_s /= angle_sum;
+        result.mag_t /= angle_sum;
+    }
+    result
+}
+
+// Triangles of a face given by `ObjData::triangulate_face`, except that quads are split along
+// their shortest diagonal in texture space like MikkTSpace does.
+pub fn tangent_triangles(data : &ObjData, face : Face) -> Vec<[usize;3]> {
+    if face.len() != 4 {
+        return data.triangulate_face(face);
+    }
+    let t = |k : usize| face.vertices[k].texcoord.map(|i| [data[i].0,data[i].1]).unwrap_or([0.;2]);
+    let p = |k : usize| {
+        let (x,y,z,_) = data[face.vertices[k].position];
+        [x,y,z]
+    };
+    let distance = |a : [Float;2], b : [Float;2]| (a[0]-b[0])*(a[0]-b[0]) + (a[1]-b[1])*(a[1]-b[1]);
+    let (d02,d13) = (distance(t(0),t(2)),distance(t(1),t(3)));
+    let diagonal_02 = if d13 < d02 {
+        false
+    } else if d02 < d13 {
+        true
+    } else {
+        let (d02,d13) = (sub(p(2),p(0)),sub(p(3),p(1)));
+        dot(d13,d13) >= dot(d02,d02)
+    };
+    if diagonal_02 {
+        vec![[0,1,2],[0,2,3]]
+    } else {
+        vec![[0,1,3],[1,2,3]]
+    }
+}
+
+impl ObjData {
+    /// Compute the tangent frame of every vertex of faces, for normal mapping.
+    ///
+    /// The frames are the ones given by MikkTSpace from the positions, texture coordinates
+    /// and normals of the vertices, in the order of `faces.vertices()`. Normals should have
+    /// a length of 1. Like MikkTSpace, quads are split along their shortest diagonal in
+    /// texture space. Faces of more than 4 vertices are split by `triangulate_face` and
+    /// faces of less than 3 vertices have the tangent `(1,0,0)`.
+    ///
+    /// Fails if a vertex of a face has no normal or texture coordinates.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+    ///                f 1/1/1 2/2/1 3/3/1 4/4/1\n";
+    /// let data = ObjData::from_str(obj_str).ok().unwrap();
+    /// let tangents = data.compute_tangents().ok().unwrap();
+    /// assert_eq!(4,tangents.len());
+    /// assert_eq!((1.,0.,0.),tangents[0].tangent);
+    /// assert_eq!((0.,1.,0.),tangents[0].bitangent);
+    /// assert_eq!(1.,tangents[0].sign);
+    /// ```
+    pub fn compute_tangents(&self) -> Result<Vec<Tangent>,TangentError> {
+        // Vertices of faces and their indices in `vertices`
+        let mut vertices : Vec<Vertex> = Vec::new();
+        let mut indices : Vec<usize> = Vec::with_capacity(self.faces.vertices().len());
+        let mut known = HashMap::new();
+        for (f,face) in self.faces.iter().enumerate() {
+            for vertex in face {
+                let (x,y,z,_) = self[vertex.position];
+                let (nx,ny,nz) = self[vertex.normal.ok_or(TangentError::MissingNormal(f))?];
+                let (u,v,_) = self[vertex.texcoord.ok_or(TangentError::MissingTexCoord(f))?];
+                let vertex = Vertex { position : [x,y,z], normal : [nx,ny,nz], texcoord : [u,v] };
+                let key = [x,y,z,nx,ny,nz,u,v].map(float_key);
+                let len = vertices.len();
+                let index = *known.entry(key).or_insert(len);
+                if index == len {
+                    vertices.push(vertex);
+                }
+                indices.push(index);
+            }
+        }
+
+        let offsets = self.faces.offsets();
+        let mut good = Vec::new();
+        let mut degenerate = Vec::new();
+        for (f,face) in self.faces.iter().enumerate() {
+            let face_vertices = &indices[offsets[f]..offsets[f+1]];
+            let corners = tangent_triangles(self,face);
+            let start = good.len();
+            let mut degenerates = 0;
+            for c in corners {
+                let v = [face_vertices[c[0]],face_vertices[c[1]],face_vertices[c[2]]];
+                let p = [vertices[v[0]].position,vertices[v[1]].position,vertices[v[2]].position];
+                let triangle = Triangle::new(f,v,c);
+                if v[0] == v[1] || v[0] == v[2] || v[1] == v[2] || p[0] == p[1] || p[0] == p[2] || p[1] == p[2] {
+                    degenerate.push(triangle);
+                    degenerates += 1;
+                } else {
+                    good.push(triangle);
+                }
+            }
+            if face.len() == 4 && degenerates == 1 {
+                good[start].quad_one_degenerate = true;
+                degenerate.last_mut().unwrap().quad_one_degenerate = true;
+            }
+        }
+
+        for triangle in &mut good {
+            triangle.init(&vertices);
+        }
+        // Both triangles of a quad should have the same orientation
+        let mut t = 0;
+        while t+1 < good.len() {
+            if good[t].face != good[t+1].face || self.faces[good[t].face].len() != 4 {
+                t += 1;
+                continue;
+            }
+            if good[t].orient != good[t+1].orient {
+                let first = good[t+1].group_with_any
+                    || texture_area(&vertices,&good[t].vertices) >= texture_area(&vertices,&good[t+1].vertices);
+                let (from,to) = if first { (t,t+1) } else { (t+1,t) };
+                good[to].orient = good[from].orient;
+            }
+            t += 2;
+        }
+
+        build_neighbors(&mut good);
+        let mut groups : Vec<Group> = Vec::new();
+        for t in 0..good.len() {
+            for i in 0..3 {
+                if good[t].group_with_any || good[t].groups[i].is_some() {
+                    continue;
+                }
+                let g = groups.len();
+                groups.push(Group { vertex : good[t].vertices[i], orient : good[t].orient, triangles : vec![t] });
+                good[t].groups[i] = Some(g);
+                if let Some(left) = good[t].neighbors[i] {
+                    assign_group(&mut good,&mut groups,g,left);
+                }
+                if let Some(right) = good[t].neighbors[(i+2)%3] {
+                    assign_group(&mut good,&mut groups,g,right);
+                }
+            }
+        }
+
+        let mut spaces = vec![TangentSpace::new(); indices.len()];
+        for group in &groups {
+            let n = vertices[group.vertex].normal;
+            let mut subgroups : Vec<(Vec<usize>,TangentSpace)> = Vec::new();
+            for &t in &group.triangles {
+                let triangle = &good[t];
+                let i = triangle.corner_of(group.vertex);
+                let (os,ot) = (project(n,triangle.os),project(n,triangle.ot));
+                // Triangles whose tangents don't point in opposite directions share their tangent space
+                let mut members : Vec<usize> = group.triangles.iter().cloned().filter(|&u| {
+                    let other = &good[u];
+                    triangle.group_with_any || other.group_with_any || triangle.face == other.face
+                        || (dot(os,project(n,other.os)) > -1. && dot(ot,project(n,other.ot)) > -1.)
+                }).collect();
+                members.sort();
+                let space = match subgroups.iter().find(|s| s.0 == members) {
+                    Some(s) => s.1,
+                    None => {
+                        let space = eval_tangent_space(&good,&members,&vertices,group.vertex);
+                        subgroups.push((members,space));
+                        space
+                    },
+                };
+                let output = &mut spaces[offsets[triangle.face] + triangle.corners[i]];
+                *output = if output.counter == 1 {
+                    TangentSpace { counter : 2, ..output.average(&space) }
+                } else {
+                    TangentSpace { counter : 1, ..space }
+                };
+                output.orient = group.orient;
+            }
+        }
+
+        // Degenerate triangles take the tangent space of the first good triangle with the same vertex
+        for triangle in degenerate.iter().filter(|t| !t.quad_one_degenerate) {
+            for i in 0..3 {
+                let source = good.iter().flat_map(|t| (0..3).map(move |j| (t,j)))
+                    .find(|&(t,j)| t.vertices[j] == triangle.vertices[i]);
+                if let Some((t,j)) = source {
+                    spaces[offsets[triangle.face] + triangle.corners[i]] = spaces[offsets[t.face] + t.corners[j]];
+                }
+            }
+        }
+        // The vertex of a quad only in its degenerate triangle takes the tangent space of a vertex at the same position
+        for triangle in good.iter().filter(|t| t.quad_one_degenerate) {
+            let missing = (1..4).find(|k| !triangle.corners.contains(k)).unwrap_or(0);
+            let offset = offsets[triangle.face];
+            let position = vertices[indices[offset + missing]].position;
+            if let Some(&k) = triangle.corners.iter().find(|&&k| vertices[indices[offset + k]].position == position) {
+                spaces[offset + missing] = spaces[offset + k];
+            }
+        }
+
+        Ok(spaces.iter().zip(&indices).map(|(space,&v)| {
+            let sign = if space.orient { 1. } else { -1. };
+            let bitangent = scale(sign,cross(vertices[v].normal,space.os));
+            Tangent {
+                tangent : (space.os[0],space.os[1],space.os[2]),
+                bitangent : (bitangent[0],bitangent[1],bitangent[2]),
+                sign,
+            }
+        }).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use tangents::*;
+
+    fn close(a : (Float,Float,Float), b : (Float,Float,Float)) -> bool {
+        (a.0-b.0).abs() < 1e-5 && (a.1-b.1).abs() < 1e-5 && (a.2-b.2).abs() < 1e-5
+    }
+
+    #[test]
+    fn mirrored_tangents() {
+        // Two quads sharing an edge, the texture of the second one being mirrored
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 0\nv 2 1 0\n\
+                       vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+                       f 1/1/1 2/2/1 3/3/1 4/4/1\nf 2/2/1 5/1/1 6/4/1 3/3/1\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let tangents = data.compute_tangents().ok().unwrap();
+        assert_eq!(8,tangents.len());
+        for t in &tangents[..4] {
+            assert_eq!(Tangent { tangent : (1.,0.,0.), bitangent : (0.,1.,0.), sign : 1. },*t);
+        }
+        for t in &tangents[4..] {
+            assert_eq!(Tangent { tangent : (-1.,0.,0.), bitangent : (0.,1.,0.), sign : -1. },*t);
+        }
+    }
+
+    #[test]
+    fn smooth_tangents() {
+        // Two quads folded along their common edge with a shared normal, and a pentagon
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nv 2 0 1\nv 2 1 1\n\
+                       vt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvt 2 0\nvt 2 1\nvn 0 0 1\nvn -0.70710678 0 0.70710678\n\
+                       f 1/1/1 2/2/2 3/3/2 4/4/1\nf 2/2/2 5/5/2 6/6/2 3/3/2\nf 1/1/1 2/2/1 6/6/1 3/3/1 4/4/1\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let tangents = data.compute_tangents().ok().unwrap();
+        // Vertices of the common edge have the same tangent in both faces
+        assert_eq!(tangents[1],tangents[4]);
+        assert_eq!(tangents[2],tangents[7]);
+        let h = 1. / (2. as Float).sqrt();
+        for t in &tangents[..8] {
+            let (x,y,z) = t.tangent;
+            assert!((x*x+y*y+z*z - 1.).abs() < 1e-5);
+            assert_eq!(1.,t.sign);
+            assert!(close((0.,1.,0.),t.bitangent));
+        }
+        assert!(close((h,0.,h),tangents[5].tangent));
+        assert_eq!(5,data.faces.get(2).unwrap().len());
+        assert_eq!(13,tangents.len());
+    }
+
+    #[test]
+    fn degenerate_tangents() {
+        // Degenerate triangles take the tangents of the quad at their vertices
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvt 1 0\nvt 1 1\nvt 0 1\nvn 0 0 1\n\
+                       f 1/1/1 2/2/1 3/3/1 4/4/1\nf 1/1/1 1/1/1 3/3/1\nf 1/1/1 2/2/1 2/2/1 3/3/1\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let tangents = data.compute_tangents().ok().unwrap();
+        assert_eq!(11,tangents.len());
+        for t in &tangents {
+            assert_eq!((1.,0.,0.),t.tangent);
+            assert_eq!(1.,t.sign);
+        }
+    }
+
+    #[test]
+    fn reference_tangents() {
+        // Quad with skewed texture coordinates, split along its shortest diagonal in texture space
+        // (2-4 here), a triangle sharing its edge 2-3 and a mirrored triangle sharing its edge 1-4
+        let obj_str = "v 0 0 0\nv 2 0.2 0.1\nv 2.3 1.8 0.4\nv -0.2 1.5 0.2\nv 3.5 0.9 0.8\nv -1.8 0.7 0.3\n\
+                       vt 0.1 0.05\nvt 0.9 0.2\nvt 0.75 0.95\nvt 0.05 0.7\nvt 1.3 0.55\nvt 0.6 0.4\n\
+                       vn 0 0 1\nvn 0.1 -0.05 0.99373\nvn -0.1 -0.15 0.98361\nvn 0.05 0.1 0.99373\nvn -0.3 0.1 0.94868\nvn 0.2 0.05 0.97852\n\
+                       f 1/1/1 2/2/2 3/3/3 4/4/4\nf 2/2/2 5/5/5 3/3/3\nf 1/1/1 4/4/4 6/6/6\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let tangents = data.compute_tangents().ok().unwrap();
+        // Tangents and signs given by the reference implementation of MikkTSpace
+        let expected = [((0.997459,-0.071247,0.),1.),((0.993037,-0.057489,-0.102823),1.),
+                        ((0.994436,-0.047978,0.093785),1.),((0.995144,-0.089456,-0.041069),1.),
+                        ((0.993037,-0.057489,-0.102823),1.),((0.951486,-0.039902,0.305094),1.),
+                        ((0.994436,-0.047978,0.093785),1.),
+                        ((-0.997981,-0.063508,0.),-1.),((-0.995997,-0.068820,0.057040),-1.),
+                        ((-0.977457,-0.058788,0.202787),-1.)];
+        assert_eq!(expected.len(),tangents.len());
+        for (t,&(tangent,sign)) in tangents.iter().zip(&expected) {
+            assert!(close(tangent,t.tangent),"{:?} {:?}",tangent,t.tangent);
+            assert_eq!(sign,t.sign);
+        }
+    }
+
+    #[test]
+    fn tangents_errors() {
+        let data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\nf 1/1/1 2/1/1 3/1/1\nf 1/1 2/1 3/1/1\n").ok().unwrap();
+        assert_eq!(Some(TangentError::MissingNormal(1)),data.compute_tangents().err());
+        let data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 0 1 0\nvn 0 0 1\nf 1//1 2//1 3//1\n").ok().unwrap();
+        assert_eq!(Some(TangentError::MissingTexCoord(0)),data.compute_tangents().err());
+    }
+}
@@ -0,0 +1,792 @@
+// Computations are done with f64, so converting vertex data does nothing with the `f64` feature.
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+
+use obj::{Float,ObjData,Object,Element};
+use face::FaceVertex;
+use freeform::{CurveType,FreeFormAttributes,Technique,CurveRef,Curve,Curve2,Surface};
+
+/// Resolution used when a curve or surface has no approximation technique.
+pub const DEFAULT_RESOLUTION : f32 = 4.;
+
+// Maximal number of subdivisions of an interval by curvature dependent approximation.
+const MAX_DEPTH : usize = 8;
+
+/// Errors of free-form tessellation.
+#[derive(PartialEq, Debug)]
+pub enum TessellationError {
+    /// The type of a curve or surface can't be evaluated.
+    /// Only B-spline and Bezier curves and surfaces are supported.
+    UnsupportedType(CurveType),
+    /// The parameter vector doesn't match the degree and the number of control points,
+    /// or the range of a surface is empty.
+    InvalidParameters,
+}
+
+/// Triangles approximating a free-form surface.
+#[derive(PartialEq, Debug)]
+pub struct SurfaceMesh {
+    /// Positions of the points.
+    pub positions : Vec<(Float,Float,Float)>,
+    /// Texture coordinates of the points.
+    pub texcoords : Vec<(Float,Float,Float)>,
+    /// Normals of the points.
+    pub normals : Vec<(Float,Float,Float)>,
+    /// Triangles given by the indices of their points.
+    pub triangles : Vec<[usize;3]>,
+}
+
+// A rational B-spline in one direction.
+struct Basis {
+    degree : usize,
+    knots : Vec<f64>,
+}
+
+impl Basis {
+    fn new(curve_type : CurveType, degree : usize, parameters : &[Float]) -> Result<Basis, TessellationError> {
+        if degree == 0 {
+            return Err(TessellationError::InvalidParameters);
+        }
+        let knots : Vec<f64> = match curve_type {
+            CurveType::BSpline => {
+                if parameters.len() < 2 * (degree + 1) {
+                    return Err(TessellationError::InvalidParameters);
+                }
+                parameters.iter().map(|&p| p as f64).collect()
+            },
+            CurveType::Bezier => {
+                // The parameters are the breakpoints between the segments
+                if parameters.len() < 2 {
+                    return Err(TessellationError::InvalidParameters);
+                }
+                let mut knots = vec![parameters[0] as f64];
+                for &p in parameters {
+                    for _ in 0..degree {
+                        knots.push(p as f64);
+                    }
+                }
+                knots.push(parameters[parameters.len()-1] as f64);
+                knots
+            },
+            t => return Err(TessellationError::UnsupportedType(t)),
+        };
+        if knots.windows(2).any(|w| w[0] > w[1]) || knots[degree] >= knots[knots.len()-degree-1] {
+            return Err(TessellationError::InvalidParameters);
+        }
+        Ok(Basis { degree, knots })
+    }
+
+    fn nb_control_points(&self) -> usize {
+        self.knots.len() - self.degree - 1
+    }
+
+    // Index of the knot span containing `u`.
+    fn span(&self, u : f64) -> usize {
+        let n = self.nb_control_points() - 1;
+        if u >= self.knots[n+1] {
+            return n;
+        }
+        if u <= self.knots[self.degree] {
+            return self.degree;
+        }
+        let mut low = self.degree;
+        let mut high = n+1;
+        while high - low > 1 {
+            let mid = (low + high) / 2;
+            if u < self.knots[mid] {
+                high = mid;
+            } else {
+                low = mid;
+            }
+        }
+        low
+    }
+
+    // Non-zero basis functions at `u`, they apply to control points `span-degree..span+1`.
+    fn functions(&self, span : usize, u : f64) -> Vec<f64> {
+        let p = self.degree;
+        let mut n = vec![1.;p+1];
+        let mut left = vec![0.;p+1];
+        let mut right = vec![0.;p+1];
+        for j in 1..p+1 {
+            left[j] = u - self.knots[span+1-j];
+            right[j] = self.knots[span+j] - u;
+            let mut saved = 0.;
+            for r in 0..j {
+                let temp = n[r] / (right[r+1] + left[j-r]);
+                n[r] = saved + right[r+1] * temp;
+                saved = left[j-r] * temp;
+            }
+            n[j] = saved;
+        }
+        n
+    }
+
+    // Distinct knots splitting `(u0,u1)` in polynomial segments, including u0 and u1.
+    fn breakpoints(&self, (u0,u1) : (f64,f64)) -> Vec<f64> {
+        let mut points = vec![u0];
+        for &k in &self.knots {
+            if k > u0 && k < u1 && k > points[points.len()-1] {
+                points.push(k);
+            }
+        }
+        points.push(u1);
+        points
+    }
+}
+
+// Homogeneous coordinates `(x*w,y*w,z*w,w)` of a control point.
+fn homogeneous((x,y,z) : (f64,f64,f64), w : f64, rational : bool) -> [f64;4] {
+    let w = if rational {w} else {1.};
+    [x*w,y*w,z*w,w]
+}
+
+fn sub(a : [f64;3], b : [f64;3]) -> [f64;3] {
+    [a[0]-b[0],a[1]-b[1],a[2]-b[2]]
+}
+
+fn cross(a : [f64;3], b : [f64;3]) -> [f64;3] {
+    [a[1]*b[2]-a[2]*b[1],a[2]*b[0]-a[0]*b[2],a[0]*b[1]-a[1]*b[0]]
+}
+
+fn length(a : [f64;3]) -> f64 {
+    (a[0]*a[0]+a[1]*a[1]+a[2]*a[2]).sqrt()
+}
+
+fn project(p : [f64;4]) -> [f64;3] {
+    [p[0]/p[3],p[1]/p[3],p[2]/p[3]]
+}
+
+// A curve which can be evaluated at a parameter.
+struct CurveEvaluator {
+    basis : Basis,
+    control_points : Vec<[f64;4]>,
+}
+
+impl CurveEvaluator {
+    fn new(attributes : &FreeFormAttributes, parameters : &[Float], control_points : Vec<[f64;4]>)
+        -> Result<CurveEvaluator, TessellationError> {
+        let basis = Basis::new(attributes.curve_type,attributes.degree.0,parameters)?;
+        if basis.nb_control_points() != control_points.len() {
+            return Err(TessellationError::InvalidParameters);
+        }
+        Ok(CurveEvaluator { basis, control_points })
+    }
+
+    fn evaluate(&self, u : f64) -> [f64;3] {
+        let span = self.basis.span(u);
+        let n = self.basis.functions(span,u);
+        let mut p = [0.;4];
+        for (i,f) in n.iter().enumerate() {
+            let c = self.control_points[span-self.basis.degree+i];
+            for k in 0..4 {
+                p[k] += f * c[k];
+            }
+        }
+        project(p)
+    }
+}
+
+// Parameters at which a curve is sampled between each of its breakpoints.
+fn samples<F : Fn(f64) -> [f64;3]>(eval : &F, breakpoints : &[f64], degree : usize,
+                                   technique : Option<Technique>, resolution : f32) -> Vec<f64> {
+    let mut params = vec![breakpoints[0]];
+    for w in breakpoints.windows(2) {
+        let (a,b) = (w[0],w[1]);
+        match technique {
+            Some(Technique::Spatial(max_length)) if max_length > 0. => {
+                let mut len = 0.;
+                let steps = 16;
+                for i in 0..steps {
+                    let t0 = a + (b-a) * i as f64 / steps as f64;
+                    let t1 = a + (b-a) * (i+1) as f64 / steps as f64;
+                    len += length(sub(eval(t1),eval(t0)));
+                }
+                let n = ((len / max_length as f64).ceil() as usize).max(1);
+                for i in 1..n+1 {
+                    params.push(a + (b-a) * i as f64 / n as f64);
+                }
+            },
+            Some(Technique::Curvature(max_dist,max_angle)) => {
+                subdivide(eval,a,b,max_dist as f64,(max_angle as f64).to_radians(),0,&mut params);
+            },
+            _ => {
+                let resolution = match technique {
+                    Some(Technique::Parametric(res,_)) | Some(Technique::ParametricTrimmed(res)) => res,
+                    _ => resolution,
+                };
+                let n = ((resolution * degree as f32).round() as usize).max(1);
+                for i in 1..n+1 {
+                    params.push(a + (b-a) * i as f64 / n as f64);
+                }
+            },
+        }
+    }
+    params
+}
+
+// Subdivide `(a,b)` until the curve is close enough to its chords,
+// the parameters after `a` are pushed in `params`.
+fn subdivide<F : Fn(f64) -> [f64;3]>(eval : &F, a : f64, b : f64, max_dist : f64, max_angle : f64,
+                                     depth : usize, params : &mut Vec<f64>) {
+    let m = (a + b) / 2.;
+    let (pa,pm,pb) = (eval(a),eval(m),eval(b));
+    let chord = sub(pb,pa);
+    let dist = if length(chord) > 0. {
+        length(cross(sub(pm,pa),chord)) / length(chord)
+    } else {
+        length(sub(pm,pa))
+    };
+    let (d1,d2) = (sub(pm,pa),sub(pb,pm));
+    let angle = if length(d1) > 0. && length(d2) > 0. {
+        let cos = (d1[0]*d2[0]+d1[1]*d2[1]+d1[2]*d2[2]) / (length(d1)*length(d2));
+        cos.clamp(-1.,1.).acos()
+    } else {
+        0.
+    };
+    if depth < MAX_DEPTH && (dist > max_dist || angle > max_angle) {
+        subdivide(eval,a,m,max_dist,max_angle,depth+1,params);
+        subdivide(eval,m,b,max_dist,max_angle,depth+1,params);
+    } else {
+        params.push(b);
+    }
+}
+
+// Even-odd test of a point against a polygon.
+fn inside((u,v) : (f64,f64), polygon : &[[f64;3]]) -> bool {
+    let mut result = false;
+    let mut j = polygon.len()-1;
+    for i in 0..polygon.len() {
+        let (pi,pj) = (polygon[i],polygon[j]);
+        if (pi[1] > v) != (pj[1] > v) && u < (pj[0]-pi[0]) * (v-pi[1]) / (pj[1]-pi[1]) + pi[0] {
+            result = !result;
+        }
+        j = i;
+    }
+    result
+}
+
+impl ObjData {
+    fn curve_evaluator(&self, curve : &Curve) -> Result<CurveEvaluator, TessellationError> {
+        let rational = curve.attributes.rational;
+        let control_points = curve.control_points.iter().map(|&i| {
+            let (x,y,z,w) = self.vertices[i];
+            homogeneous((x as f64,y as f64,z as f64),w as f64,rational)
+        }).collect();
+        CurveEvaluator::new(&curve.attributes,&curve.parameters,control_points)
+    }
+
+    fn curve2_evaluator(&self, curve : &Curve2) -> Result<CurveEvaluator, TessellationError> {
+        let rational = curve.attributes.rational;
+        let control_points = curve.control_points.iter().map(|&i| {
+            let (u,v,w) = self.parameter_vertices[i];
+            homogeneous((u as f64,v as f64,0.),w as f64,rational)
+        }).collect();
+        CurveEvaluator::new(&curve.attributes,&curve.parameters,control_points)
+    }
+
+    /// Evaluate the free-form curve `i` as a list of points.
+    ///
+    /// The curve is sampled according to its `ctech` approximation technique,
+    /// or with a resolution of `DEFAULT_RESOLUTION` if it has none.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 1 0\nv 2 0 0\ncstype bezier\ndeg 2\ncurv 0 1 1 2 3\nparm u 0 1\nend\n";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let data = ObjData::load(&mut input).ok().unwrap();
+    /// let points = data.evaluate_curve(0).ok().unwrap();
+    /// assert_eq!((1.,0.5,0.),points[points.len()/2]);
+    /// ```
+    pub fn evaluate_curve(&self, i : usize) -> Result<Vec<(Float,Float,Float)>, TessellationError> {
+        let curve = &self.curves[i];
+        let evaluator = self.curve_evaluator(curve)?;
+        let range = (curve.range.0 as f64,curve.range.1 as f64);
+        let eval = |u| evaluator.evaluate(u);
+        let params = samples(&eval,&evaluator.basis.breakpoints(range),evaluator.basis.degree,
+                             curve.attributes.curve_technique,DEFAULT_RESOLUTION);
+        Ok(params.iter().map(|&u| {
+            let p = eval(u);
+            (p[0] as Float,p[1] as Float,p[2] as Float)
+        }).collect())
+    }
+
+    // Polygon in parameter space of a trimming loop.
+    fn trimming_loop(&self, refs : &[CurveRef]) -> Result<Vec<[f64;3]>, TessellationError> {
+        let mut polygon = Vec::new();
+        for r in refs {
+            let curve = &self.curves2[r.curve];
+            let evaluator = self.curve2_evaluator(curve)?;
+            let range = (r.range.0 as f64,r.range.1 as f64);
+            let eval = |u| evaluator.evaluate(u);
+            // The range can go backward along the curve
+            let (low,high) = if range.0 <= range.1 {range} else {(range.1,range.0)};
+            let mut params = samples(&eval,&evaluator.basis.breakpoints((low,high)),evaluator.basis.degree,
+                                     curve.attributes.curve_technique,DEFAULT_RESOLUTION);
+            if range.0 > range.1 {
+                params.reverse();
+            }
+            polygon.extend(params.iter().map(|&u| eval(u)));
+        }
+        Ok(polygon)
+    }
+
+    /// Evaluate the free-form surface `i` as a mesh of triangles.
+    ///
+    /// The surface is sampled on a grid according to its `stech` approximation technique,
+    /// or with a resolution of `DEFAULT_RESOLUTION` if it has none.
+    /// Triangles whose center is outside the `trim` loops or inside a `hole` loop are removed.
+    /// Texture coordinates are the parameters normalized to `[0,1]`.
+    pub fn evaluate_surface(&self, i : usize)
+        -> Result<SurfaceMesh, TessellationError> {
+        let surface : &Surface = &self.surfaces[i];
+        if surface.range_u.0 == surface.range_u.1 || surface.range_v.0 == surface.range_v.1 {
+            return Err(TessellationError::InvalidParameters);
+        }
+        let attributes = &surface.attributes;
+        let basis_u = Basis::new(attributes.curve_type,attributes.degree.0,&surface.parameters_u)?;
+        let basis_v = Basis::new(attributes.curve_type,attributes.degree.1,&surface.parameters_v)?;
+        // Control points are listed along u first
+        let nb_u = basis_u.nb_control_points();
+        if nb_u * basis_v.nb_control_points() != surface.control_points.len() {
+            return Err(TessellationError::InvalidParameters);
+        }
+        let rational = attributes.rational;
+        let control_points : Vec<[f64;4]> = surface.control_points.iter().map(|&(v,_,_)| {
+            let (x,y,z,w) = self.vertices[v];
+            homogeneous((x as f64,y as f64,z as f64),w as f64,rational)
+        }).collect();
+
+        let eval = |u : f64, v : f64| -> [f64;3] {
+            let (span_u,span_v) = (basis_u.span(u),basis_v.span(v));
+            let (nu,nv) = (basis_u.functions(span_u,u),basis_v.functions(span_v,v));
+            let mut p = [0.;4];
+            for (j,fv) in nv.iter().enumerate() {
+                for (i,fu) in nu.iter().enumerate() {
+                    let c = control_points[(span_v-basis_v.degree+j)*nb_u + span_u-basis_u.degree+i];
+                    for k in 0..4 {
+                        p[k] += fu * fv * c[k];
+                    }
+                }
+            }
+            project(p)
+        };
+
+        let range_u = (surface.range_u.0 as f64,surface.range_u.1 as f64);
+        let range_v = (surface.range_v.0 as f64,surface.range_v.1 as f64);
+        let (mid_u,mid_v) = ((range_u.0+range_u.1)/2.,(range_v.0+range_v.1)/2.);
+        let (technique_u,technique_v,res_u,res_v) = match attributes.surface_technique {
+            Some(Technique::Parametric(ures,vres)) => (None,None,ures,vres),
+            Some(Technique::ParametricTrimmed(res)) => (None,None,res,res),
+            t => (t,t,DEFAULT_RESOLUTION,DEFAULT_RESOLUTION),
+        };
+        let params_u = samples(&|u| eval(u,mid_v),&basis_u.breakpoints(range_u),basis_u.degree,technique_u,res_u);
+        let params_v = samples(&|v| eval(mid_u,v),&basis_v.breakpoints(range_v),basis_v.degree,technique_v,res_v);
+
+        let mut trims = Vec::new();
+        for refs in &surface.trims {
+            trims.push(self.trimming_loop(refs)?);
+        }
+        let mut holes = Vec::new();
+        for refs in &surface.holes {
+            holes.push(self.trimming_loop(refs)?);
+        }
+        let keep = |p : (f64,f64)| {
+            (trims.is_empty() || trims.iter().any(|t| inside(p,t))) && !holes.iter().any(|h| inside(p,h))
+        };
+
+        // Grid points are only evaluated when used by a triangle
+        let mut indices : Vec<Option<usize>> = vec![None;params_u.len()*params_v.len()];
+        let mut mesh = SurfaceMesh {
+            positions : Vec::new(),
+            texcoords : Vec::new(),
+            normals : Vec::new(),
+            triangles : Vec::new(),
+        };
+        let h = 1e-4;
+        let mut point = |i : usize, j : usize, mesh : &mut SurfaceMesh| -> usize {
+            let k = j*params_u.len() + i;
+            if let Some(p) = indices[k] {
+                return p;
+            }
+            let (u,v) = (params_u[i],params_v[j]);
+            let p = eval(u,v);
+            // Normal from the partial derivatives estimated by central differences
+            let du = (range_u.1-range_u.0) * h;
+            let dv = (range_v.1-range_v.0) * h;
+            let su = sub(eval((u+du).min(range_u.1),v),eval((u-du).max(range_u.0),v));
+            let sv = sub(eval(u,(v+dv).min(range_v.1)),eval(u,(v-dv).max(range_v.0)));
+            let n = cross(su,sv);
+            let l = length(n);
+            let n = if l > 0. {[n[0]/l,n[1]/l,n[2]/l]} else {[0.,0.,1.]};
+            let t = ((u-range_u.0)/(range_u.1-range_u.0),(v-range_v.0)/(range_v.1-range_v.0));
+            mesh.positions.push((p[0] as Float,p[1] as Float,p[2] as Float));
+            mesh.texcoords.push((t.0 as Float,t.1 as Float,0.));
+            mesh.normals.push((n[0] as Float,n[1] as Float,n[2] as Float));
+            indices[k] = Some(mesh.positions.len()-1);
+            mesh.positions.len()-1
+        };
+        for j in 0..params_v.len()-1 {
+            for i in 0..params_u.len()-1 {
+                let (u0,u1) = (params_u[i],params_u[i+1]);
+                let (v0,v1) = (params_v[j],params_v[j+1]);
+                if keep(((2.*u0+u1)/3.,(2.*v0+v1)/3.)) {
+                    let t = [point(i,j,&mut mesh),point(i+1,j,&mut mesh),point(i,j+1,&mut mesh)];
+                    mesh.triangles.push(t);
+                }
+                if keep(((u0+2.*u1)/3.,(v0+2.*v1)/3.)) {
+                    let t = [point(i+1,j,&mut mesh),point(i+1,j+1,&mut mesh),point(i,j+1,&mut mesh)];
+                    mesh.triangles.push(t);
+                }
+            }
+        }
+        Ok(mesh)
+    }
+
+    /// Tessellate every free-form curve into a line and every free-form surface into triangles.
+    ///
+    /// The generated elements are added to `vertices`, `texcoords`, `normals`, `lines` and `faces`.
+    /// Each one is placed right after its free-form element in its object and is added to its groups,
+    /// faces taking the material and the smoothing group of their surface. Elements of free-form
+    /// elements in no object are added to the last object. The free-form elements are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::io::BufReader;
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\ncstype bspline\ndeg 1 1\n\
+    ///                surf 0 1 0 1 1 2 3 4\nparm u 0 0 1 1\nparm v 0 0 1 1\nend\n";
+    /// let mut input = BufReader::new(obj_str.as_bytes());
+    /// let mut data = ObjData::load(&mut input).ok().unwrap();
+    /// assert!(data.tessellate().is_ok());
+    /// assert_eq!(32,data.faces.len());
+    /// ```
+    pub fn tessellate(&mut self) -> Result<(), TessellationError> {
+        let mut lines = Vec::new();
+        for i in 0..self.curves.len() {
+            lines.push(self.evaluate_curve(i)?);
+        }
+        let mut surfaces = Vec::new();
+        for i in 0..self.surfaces.len() {
+            surfaces.push(self.evaluate_surface(i)?);
+        }
+        if lines.is_empty() && surfaces.is_empty() {
+            return Ok(());
+        }
+        // Line of each curve and faces of each surface
+        let mut curve_lines = Vec::with_capacity(lines.len());
+        for points in lines {
+            let first = self.vertices.len();
+            for &(x,y,z) in &points {
+                self.vertices.push((x,y,z,1.));
+            }
+            self.lines.push((first..self.vertices.len()).map(|v| (v,None)).collect());
+            curve_lines.push(self.lines.len()-1);
+        }
+        let mut surface_faces = Vec::with_capacity(surfaces.len());
+        for (k,mesh) in surfaces.into_iter().enumerate() {
+            let (v0,vt0,vn0) = (self.vertices.len(),self.texcoords.len(),self.normals.len());
+            for &(x,y,z) in &mesh.positions {
+                self.vertices.push((x,y,z,1.));
+            }
+            self.texcoords.extend(mesh.texcoords);
+            self.normals.extend(mesh.normals);
+            let first = self.faces.len();
+            for t in mesh.triangles {
+                self.faces.push(t.iter().map(|&i| FaceVertex::new(v0+i,Some(vt0+i),Some(vn0+i))));
+                self.face_materials.push(self.surface_materials.get(k).and_then(|m| *m));
+                self.smoothing_groups.push(self.surface_smoothing_groups.get(k).cloned().unwrap_or(0));
+            }
+            surface_faces.push(first..self.faces.len());
+        }
+
+        let mut curves_found = vec![false; curve_lines.len()];
+        let mut surfaces_found = vec![false; surface_faces.len()];
+        for object in &mut self.objects {
+            let mut tessellated = Object::new(object.name.clone());
+            for &element in object.written_elements().iter() {
+                tessellated.push(element);
+                match element {
+                    Element::Curve(i) if i < curve_lines.len() => {
+                        tessellated.push(Element::Line(curve_lines[i]));
+                        curves_found[i] = true;
+                    },
+                    Element::Surface(i) if i < surface_faces.len() => {
+                        for f in surface_faces[i].clone() {
+                            tessellated.push(Element::Face(f));
+                        }
+                        surfaces_found[i] = true;
+                    },
+                    _ => {},
+                }
+            }
+            *object = tessellated;
+        }
+        if curves_found.contains(&false) || surfaces_found.contains(&false) {
+            if self.objects.is_empty() {
+                self.objects.push(Object::new(String::new()));
+            }
+            let object = self.objects.last_mut().unwrap();
+            for i in (0..curve_lines.len()).filter(|&i| !curves_found[i]) {
+                object.push(Element::Line(curve_lines[i]));
+            }
+            for i in (0..surface_faces.len()).filter(|&i| !surfaces_found[i]) {
+                for f in surface_faces[i].clone() {
+                    object.push(Element::Face(f));
+                }
+            }
+        }
+
+        // The generated elements come after the others so the lists of groups stay sorted
+        for group in &mut self.groups {
+            for &i in group.curves.iter().filter(|&&i| i < curve_lines.len()) {
+                group.lines.push(curve_lines[i]);
+            }
+            for &i in group.surfaces.iter().filter(|&&i| i < surface_faces.len()) {
+                group.indexes.extend(surface_faces[i].clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use obj::*;
+    use face::{Faces,FaceVertex};
+    use tessellate::*;
+
+    fn load(obj_str : &str) -> ObjData {
+        let mut input = BufReader::new(obj_str.as_bytes());
+        ObjData::load(&mut input).ok().unwrap()
+    }
+
+    fn close(a : (Float,Float,Float), b : (Float,Float,Float)) -> bool {
+        (a.0-b.0).abs() < 1e-4 && (a.1-b.1).abs() < 1e-4 && (a.2-b.2).abs() < 1e-4
+    }
+
+    #[test]
+    fn evaluate_bezier_curve() {
+        let data = load(
+        r#"v 0 0 0
+        v 1 2 0
+        v 2 0 0
+        cstype bezier
+        deg 2
+        ctech cparm 2
+        curv 0 1 1 2 3
+        parm u 0 1
+        end"#);
+
+        let points = data.evaluate_curve(0).ok().unwrap();
+        assert_eq!(5,points.len());
+        assert!(close((0.,0.,0.),points[0]));
+        assert!(close((0.5,0.75,0.),points[1]));
+        assert!(close((1.,1.,0.),points[2]));
+        assert!(close((2.,0.,0.),points[4]));
+    }
+
+    #[test]
+    fn evaluate_rational_curve() {
+        // Quarter of a unit circle
+        let data = load(
+        r#"v 1 0 0 1
+        v 1 1 0 0.70710678
+        v 0 1 0 1
+        cstype rat bspline
+        deg 2
+        ctech curv 0.001 5
+        curv 0 1 1 2 3
+        parm u 0 0 0 1 1 1
+        end"#);
+
+        let points = data.evaluate_curve(0).ok().unwrap();
+        assert!(points.len() > 4);
+        for p in points {
+            assert!(((p.0*p.0 + p.1*p.1).sqrt() - 1.).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn evaluate_curve_spatial() {
+        let data = load(
+        r#"v 0 0 0
+        v 10 0 0
+        cstype bspline
+        deg 1
+        ctech cspace 2.5
+        curv 0 1 1 2
+        parm u 0 0 1 1
+        end"#);
+
+        assert_eq!(5,data.evaluate_curve(0).ok().unwrap().len());
+    }
+
+    #[test]
+    fn evaluate_curve_errors() {
+        let data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        cstype bspline
+        deg 1
+        curv 0 1 1 2
+        parm u 0 1 1
+        end
+        cstype cardinal
+        curv 0 1 1 2
+        parm u 0 1
+        end"#);
+
+        assert_eq!(Err(TessellationError::InvalidParameters),data.evaluate_curve(0));
+        assert_eq!(Err(TessellationError::UnsupportedType(CurveType::Cardinal)),data.evaluate_curve(1));
+    }
+
+    #[test]
+    fn evaluate_surface() {
+        let data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 1
+        cstype bspline
+        deg 1 1
+        stech cparma 2 1
+        surf 0 1 0 1 1 2 3 4
+        parm u 0 0 1 1
+        parm v 0 0 1 1
+        end"#);
+
+        let mesh = data.evaluate_surface(0).ok().unwrap();
+        assert_eq!(6,mesh.positions.len());
+        assert_eq!(4,mesh.triangles.len());
+        let center = mesh.texcoords.iter().position(|&t| close(t,(0.5,0.,0.))).unwrap();
+        assert!(close((0.5,0.,0.),mesh.positions[center]));
+        let n = mesh.normals[center];
+        assert!(close((0.,-0.5/(1.25 as Float).sqrt(),1./(1.25 as Float).sqrt()),n));
+    }
+
+    #[test]
+    fn evaluate_trimmed_surface() {
+        let data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 0
+        vp 0.25 0.25
+        vp 0.75 0.25
+        vp 0.75 0.75
+        vp 0.25 0.75
+        cstype bspline
+        deg 1
+        curv2 1 2 3 4 1
+        parm u 0 0 1 2 3 4 4
+        end
+        deg 1 1
+        stech cparma 4 4
+        surf 0 1 0 1 1 2 3 4
+        parm u 0 0 1 1
+        parm v 0 0 1 1
+        trim 0 4 1
+        end"#);
+
+        let mesh = data.evaluate_surface(0).ok().unwrap();
+        assert_eq!(8,mesh.triangles.len());
+        for (x,y,_) in mesh.positions {
+            assert!((0.25..=0.75).contains(&x) && (0.25..=0.75).contains(&y));
+        }
+    }
+
+    #[test]
+    fn tessellate() {
+        let mut data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 0
+        o Patch
+        g patches
+        usemtl Red
+        s 1
+        cstype bspline
+        deg 1 1
+        stech cparma 1 1
+        surf 0 1 0 1 1/1/1 2/2/1 3/3/1 4/4/1
+        parm u 0 0 1 1
+        parm v 0 0 1 1
+        end
+        o Guide
+        ctech cparm 1
+        curv 0 1 1 4
+        parm u 0 0 1 1
+        end
+        vt 0 0
+        vt 1 0
+        vt 0 1
+        vt 1 1
+        vn 0 0 1"#);
+
+        // Surfaces keep their material and smoothing group when written
+        assert_eq!(vec![Some(0)],data.surface_materials);
+        let reload = ObjData::from_str(&data.to_string()).ok().unwrap();
+        assert_eq!(data.surface_materials,reload.surface_materials);
+        assert_eq!(vec![1],reload.surface_smoothing_groups);
+
+        assert!(data.tessellate().is_ok());
+        assert_eq!(4+2+4,data.vertices.len());
+        assert_eq!(vec![vec![(4,None),(5,None)]],data.lines);
+        let faces : Faces = [[(6,Some(4),Some(1)),(7,Some(5),Some(2)),(8,Some(6),Some(3))],
+        [(7,Some(5),Some(2)),(9,Some(7),Some(4)),(8,Some(6),Some(3))]].iter().map(|face| face.iter().map(|&v| FaceVertex::from(v))).collect();
+        assert_eq!(faces,data.faces);
+        // Generated elements follow their free-form element
+        assert_eq!(vec![Element::Surface(0),Element::Face(0),Element::Face(1)],data.objects[0].elements);
+        assert_eq!(vec![0,1],data.objects[0].primitives);
+        assert_eq!(vec![Element::Curve(0),Element::Line(0)],data.objects[1].elements);
+        assert_eq!(vec![0],data.objects[1].lines);
+        assert_eq!(vec![0,1],data.groups[0].indexes);
+        assert_eq!(vec![0],data.groups[0].lines);
+        assert_eq!(vec![Some(0),Some(0)],data.face_materials);
+        assert_eq!(vec![1,1],data.smoothing_groups);
+
+        // Elements of free-form elements in no object go to the last object
+        let mut data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        cstype bspline
+        deg 1
+        curv 0 1 1 2
+        parm u 0 0 1 1
+        end"#);
+
+        data.objects.clear();
+        assert!(data.tessellate().is_ok());
+        assert_eq!(vec![Element::Line(0)],data.objects[0].elements);
+    }
+
+    #[test]
+    fn evaluate_surface_errors() {
+        let data = load(
+        r#"v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        v 1 1 1
+        cstype bspline
+        deg 1 1
+        surf 0 1 0.5 0.5 1 2 3 4
+        parm u 0 0 1 1
+        parm v 0 0 1 1
+        end
+        surf 0 1 0 1 1 2 3 4
+        parm u 0 0 1 1
+        parm v 0 1
+        end"#);
+
+        assert_eq!(Err(TessellationError::InvalidParameters),data.evaluate_surface(0));
+        assert_eq!(Err(TessellationError::InvalidParameters),data.evaluate_surface(1));
+    }
+}
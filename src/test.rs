@@ -1,7 +1,13 @@
+use std::env;
+use std::fs;
 use std::fs::File;
 use std::io::BufReader;
+use std::io::Cursor;
 use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process;
 use obj::*;
+use face::faces;
 
 #[test]
 fn load() {
@@ -20,7 +26,7 @@ fn load() {
     (0.,0.,1.),
     (-1.,0.,0.),
     (0.,0.,-1.)];
-    expected.faces = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+    expected.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
     vec![(7,None,Some(1)), (5,None,Some(1)), (4,None,Some(1))],
     vec![(4,None,Some(2)), (1,None,Some(2)), (0,None,Some(2))],
     vec![(5,None,Some(3)), (2,None,Some(3)), (1,None,Some(3))],
@@ -32,23 +38,44 @@ fn load() {
     vec![(5,None,Some(3)), (6,None,Some(3)), (2,None,Some(3))],
     vec![(2,None,Some(4)), (6,None,Some(4)), (7,None,Some(4))],
     vec![(0,None,Some(5)), (3,None,Some(5)), (7,None,Some(5))],
-    ];
+    ]);
     let obj = Object {
         name : String::from("Cube"),
-        primitives : vec![0,1,2,3,4,5,6,7,8,9,10,11]
+        primitives : vec![0,1,2,3,4,5,6,7,8,9,10,11],
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
+        elements : (0..12).map(Element::Face).collect(),
     };
     expected.objects = vec![obj];
     let gr1 = Group {
         name : String::from("group1"),
-        indexes : vec!(3,4,5,6,7,8).into_iter().collect()
+        indexes : vec!(3,4,5,6,7,8).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     let gr2 = Group {
         name : String::from("group2"),
-        indexes : vec!(3,4,5).into_iter().collect()
+        indexes : vec!(3,4,5).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     let gr3 = Group {
         name : String::from("group3"),
-        indexes : vec!(9,10,11).into_iter().collect()
+        indexes : vec!(9,10,11).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     expected.groups = vec![gr1,gr2,gr3];
     let f = File::open("cube.obj").unwrap();
@@ -79,7 +106,7 @@ fn write() {
     (0.,0.,1.),
     (-1.,0.,0.),
     (0.,0.,-1.)];
-    expected.faces = vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
+    expected.faces = faces(vec![ vec![(1,None,Some(0)), (3,None,Some(0)), (0,None,Some(0))],
     vec![(7,None,Some(1)), (5,None,Some(1)), (4,None,Some(1))],
     vec![(4,None,Some(2)), (1,None,Some(2)), (0,None,Some(2))],
     vec![(5,None,Some(3)), (2,None,Some(3)), (1,None,Some(3))],
@@ -91,23 +118,44 @@ fn write() {
     vec![(5,None,Some(3)), (6,None,Some(3)), (2,None,Some(3))],
     vec![(2,None,Some(4)), (6,None,Some(4)), (7,None,Some(4))],
     vec![(0,None,Some(5)), (3,None,Some(5)), (7,None,Some(5))],
-    ];
+    ]);
     let obj = Object {
         name : String::from("Cube"),
-        primitives : vec![0,1,2,3,4,5,6,7,8,9,10,11]
+        primitives : vec![0,1,2,3,4,5,6,7,8,9,10,11],
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
+        elements : (0..12).map(Element::Face).collect(),
     };
     expected.objects = vec![obj];
     let gr1 = Group {
         name : String::from("group1"),
-        indexes : vec!(3,4,5,6,7,8).into_iter().collect()
+        indexes : vec!(3,4,5,6,7,8).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     let gr2 = Group {
         name : String::from("group2"),
-        indexes : vec!(3,4,5).into_iter().collect()
+        indexes : vec!(3,4,5).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     let gr3 = Group {
         name : String::from("group3"),
-        indexes : vec!(9,10,11).into_iter().collect()
+        indexes : vec!(9,10,11).into_iter().collect(),
+        lines : Vec::new(),
+        points : Vec::new(),
+        curves : Vec::new(),
+        curves2 : Vec::new(),
+        surfaces : Vec::new(),
     };
     expected.groups = vec![gr1,gr2,gr3];
     {
@@ -145,4 +193,89 @@ fn read_write_read() {
     assert_eq!(reload.faces,data.faces);
     assert_eq!(reload.objects,data.objects);
     assert_eq!(reload.groups,data.groups);
+    assert_eq!(reload.smoothing_groups,data.smoothing_groups);
+}
+
+#[test]
+fn read_write_relative_read() {
+    let f = File::open("cube.obj").unwrap();
+    let mut input = BufReader::new(f);
+    let data = ObjData::load(&mut input).ok().unwrap();
+    let mut output = BufWriter::new(Vec::<u8>::new());
+    let options = WriteOptions { relative_indices : true };
+    assert!(data.write_with_options(&mut output,&options).is_ok());
+    let buf = output.into_inner().unwrap();
+    let mut input = BufReader::new(&buf[..]);
+    let reload = ObjData::load(&mut input).ok().unwrap();
+    assert_eq!(reload.vertices,data.vertices);
+    assert_eq!(reload.normals,data.normals);
+    assert_eq!(reload.texcoords,data.texcoords);
+    assert_eq!(reload.faces,data.faces);
+    assert_eq!(reload.objects,data.objects);
+    assert_eq!(reload.groups,data.groups);
+    assert_eq!(reload.smoothing_groups,data.smoothing_groups);
+}
+
+#[test]
+fn load_cursor_write_vec() {
+    let f = File::open("cube.obj").unwrap();
+    let data = ObjData::load(&mut BufReader::new(f)).ok().unwrap();
+    let mut output : Vec<u8> = Vec::new();
+    assert!(data.write(&mut output).is_ok());
+    let reload = ObjData::load(&mut Cursor::new(&output)).ok().unwrap();
+    assert_eq!(reload.vertices,data.vertices);
+    assert_eq!(reload.faces,data.faces);
+    assert_eq!(data.to_string().as_bytes(),&output[..]);
+    let reparsed : ObjData = data.to_string().parse().ok().unwrap();
+    assert_eq!(reparsed.faces,data.faces);
+}
+
+#[test]
+fn from_path() {
+    let data = ObjData::from_path("cube.obj").ok().unwrap();
+    assert_eq!(8,data.vertices.len());
+    assert_eq!(12,data.faces.len());
+    match ObjData::from_path("missing.obj").err().unwrap() {
+        LoadingError::Io(err) => assert!(err.to_string().starts_with("missing.obj: ")),
+        _ => panic!(),
+    };
+}
+
+// Temporary directory removed when dropped, even if the test fails.
+struct TempDir(PathBuf);
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn from_path_with_materials() {
+    // The process id keeps concurrent test runs apart
+    let temp = TempDir(env::temp_dir().join(format!("lwobj_from_path_with_materials_{}",process::id())));
+    let dir = &temp.0;
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("scene.obj"),"mtllib scene.mtl\nv 0 0 0\nusemtl Red\np 1\n").unwrap();
+    fs::write(dir.join("scene.mtl"),"newmtl Red\nKd 1 0 0\n").unwrap();
+    let (data,libraries) = ObjData::from_path_with_materials(dir.join("scene.obj")).ok().unwrap();
+    assert_eq!(vec![String::from("Red")],data.materials);
+    assert_eq!(1,libraries.len());
+    assert_eq!("Red",libraries[0].materials[0].name);
+    assert_eq!(Some((1.,0.,0.)),libraries[0].materials[0].diffuse);
+
+    fs::remove_file(dir.join("scene.mtl")).unwrap();
+    assert!(ObjData::from_path_with_materials(dir.join("scene.obj")).is_err());
+}
+
+#[cfg(feature = "f64")]
+#[test]
+fn load_write_f64() {
+    let obj_str = "v 4512345.123456789 5412345.987654321 -12.000000001\nvn 0.1 0.2 0.3\nv 1 2 3\nv 1 3 2\nf 1//1 2//1 3//1\n";
+    let data = ObjData::from_str(obj_str).ok().unwrap();
+    assert_eq!((4512345.123456789,5412345.987654321,-12.000000001,1.),data.vertices[0]);
+    assert_eq!(data.vertices,ObjData::load(&mut obj_str.as_bytes()).ok().unwrap().vertices);
+    let reload = ObjData::from_str(&data.to_string()).ok().unwrap();
+    assert_eq!(data.vertices,reload.vertices);
+    assert_eq!(data.normals,reload.normals);
 }
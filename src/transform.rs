@@ -0,0 +1,204 @@
+use obj::{Float,ObjData};
+
+// Determinant of the linear part of `m`.
+fn determinant(m : &[[Float;4];4]) -> Float {
+    m[0][0] * (m[1][1]*m[2][2] - m[1][2]*m[2][1])
+        - m[0][1] * (m[1][0]*m[2][2] - m[1][2]*m[2][0])
+        + m[0][2] * (m[1][0]*m[2][1] - m[1][1]*m[2][0])
+}
+
+// Cofactors of the linear part of `m`, its inverse transpose multiplied by its determinant.
+fn cofactors(m : &[[Float;4];4]) -> [[Float;3];3] {
+    let mut c = [[0.;3];3];
+    for (i,row) in c.iter_mut().enumerate() {
+        let (i1,i2) = ((i+1)%3,(i+2)%3);
+        for (j,value) in row.iter_mut().enumerate() {
+            let (j1,j2) = ((j+1)%3,(j+2)%3);
+            *value = m[i1][j1]*m[i2][j2] - m[i1][j2]*m[i2][j1];
+        }
+    }
+    c
+}
+
+impl ObjData {
+    /// Apply the affine transform `matrix` to vertices and normals.
+    ///
+    /// Vertices `(x,y,z)` become `matrix * (x,y,z,1)`, the last row of `matrix` being ignored,
+    /// and their weight w is unchanged. Normals are transformed by the inverse transpose of
+    /// the linear part of `matrix` and normalized. When `matrix` mirrors space, the order of
+    /// the vertices of faces is reversed so that they keep facing their normals.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::from_str("v 0 0 0 1\nv 1 0 0 0.5\nv 0 1 0 1\nvn 0 0 1\nf 1//1 2//1 3//1\n").ok().unwrap();
+    /// // Scale x by 2, mirror z and translate by (1,0,0)
+    /// data.transform([[2.,0.,0.,1.],[0.,1.,0.,0.],[0.,0.,-1.,0.],[0.,0.,0.,1.]]);
+    /// assert_eq!((3.,0.,0.,0.5),data.vertices[1]);
+    /// assert_eq!((0.,0.,-1.),data.normals[0]);
+    /// let positions : Vec<_> = data.faces.get(0).unwrap().positions().map(|v| v.0).collect();
+    /// assert_eq!(vec![2,1,0],positions);
+    /// ```
+    pub fn transform(&mut self, matrix : [[Float;4];4]) {
+        let m = &matrix;
+        for vertex in &mut self.vertices {
+            let (x,y,z,w) = *vertex;
+            *vertex = (m[0][0]*x + m[0][1]*y + m[0][2]*z + m[0][3],
+                       m[1][0]*x + m[1][1]*y + m[1][2]*z + m[1][3],
+                       m[2][0]*x + m[2][1]*y + m[2][2]*z + m[2][3],
+                       w);
+        }
+        let determinant = determinant(m);
+        let sign = if determinant < 0. { -1. } else { 1. };
+        let c = cofactors(m);
+        for normal in &mut self.normals {
+            let (x,y,z) = *normal;
+            let n = [c[0][0]*x + c[0][1]*y + c[0][2]*z,
+                     c[1][0]*x + c[1][1]*y + c[1][2]*z,
+                     c[2][0]*x + c[2][1]*y + c[2][2]*z];
+            let length = (n[0]*n[0] + n[1]*n[1] + n[2]*n[2]).sqrt();
+            if length > 0. {
+                let s = sign / length;
+                *normal = (s*n[0],s*n[1],s*n[2]);
+            }
+        }
+        if determinant < 0. {
+            for i in 0..self.faces.len() {
+                self.faces[i].reverse();
+            }
+        }
+    }
+
+    /// Multiply the coordinates of vertices by `factor`, to change their unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// // From centimetres to metres
+    /// let mut data = ObjData::from_str("v 0 200 50\n").ok().unwrap();
+    /// data.scale(0.01);
+    /// assert_eq!((0.,2.,0.5,1.),data.vertices[0]);
+    /// ```
+    pub fn scale(&mut self, factor : Float) {
+        self.transform([[factor,0.,0.,0.],[0.,factor,0.,0.],[0.,0.,factor,0.],[0.,0.,0.,1.]]);
+    }
+
+    /// Move vertices so that the center of their bounding box is the origin.
+    pub fn recenter(&mut self) {
+        if let Some(bounds) = self.bounds() {
+            let (x,y,z) = bounds.center();
+            self.transform([[1.,0.,0.,-x],[0.,1.,0.,-y],[0.,0.,1.,-z],[0.,0.,0.,1.]]);
+        }
+    }
+
+    /// Move and scale vertices uniformly so that their bounding box is centered on the origin
+    /// and its largest side has a length of 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::from_str("v 10 10 10\nv 14 12 10\n").ok().unwrap();
+    /// data.scale_to_unit_box();
+    /// let bounds = data.bounds().unwrap();
+    /// assert_eq!((-0.5,-0.25,0.),bounds.min);
+    /// assert_eq!((0.5,0.25,0.),bounds.max);
+    /// ```
+    pub fn scale_to_unit_box(&mut self) {
+        self.recenter();
+        if let Some(bounds) = self.bounds() {
+            let (x,y,z) = bounds.size();
+            let size = x.max(y).max(z);
+            if size > 0. {
+                self.scale(1. / size);
+            }
+        }
+    }
+
+    /// Rotate vertices and normals from a convention where y is up to one where z is up.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let mut data = ObjData::from_str("v 1 2 3\nvn 0 1 0\n").ok().unwrap();
+    /// data.y_up_to_z_up();
+    /// assert_eq!((1.,-3.,2.,1.),data.vertices[0]);
+    /// assert_eq!((0.,0.,1.),data.normals[0]);
+    /// data.z_up_to_y_up();
+    /// assert_eq!((1.,2.,3.,1.),data.vertices[0]);
+    /// ```
+    pub fn y_up_to_z_up(&mut self) {
+        self.transform([[1.,0.,0.,0.],[0.,0.,-1.,0.],[0.,1.,0.,0.],[0.,0.,0.,1.]]);
+    }
+
+    /// Rotate vertices and normals from a convention where z is up to one where y is up.
+    pub fn z_up_to_y_up(&mut self) {
+        self.transform([[1.,0.,0.,0.],[0.,0.,1.,0.],[0.,-1.,0.,0.],[0.,0.,0.,1.]]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+
+    fn close(a : (Float,Float,Float), b : (Float,Float,Float)) -> bool {
+        (a.0-b.0).abs() < 1e-5 && (a.1-b.1).abs() < 1e-5 && (a.2-b.2).abs() < 1e-5
+    }
+
+    #[test]
+    fn transform_normals() {
+        // The normal of a sloped face stays orthogonal to it when stretching along x
+        let obj_str = "v 0 0 0\nv 1 0 1\nv 0 1 0\nvn -0.70710677 0 0.70710677\nf 1//1 2//1 3//1\n";
+        let mut data = ObjData::from_str(obj_str).ok().unwrap();
+        data.transform([[2.,0.,0.,0.],[0.,1.,0.,0.],[0.,0.,1.,5.],[0.,0.,0.,1.]]);
+        assert_eq!((2.,0.,6.,1.),data.vertices[1]);
+        let n = data.normals[0];
+        let (a,b) = (data.vertices[0],data.vertices[1]);
+        assert!(((b.0-a.0)*n.0 + (b.1-a.1)*n.1 + (b.2-a.2)*n.2).abs() < 1e-6);
+        let s = 1. / (5. as Float).sqrt();
+        assert!(close((-s,0.,2.*s),n));
+        assert_eq!(vec![0,1,2],data.faces.get(0).unwrap().positions().map(|v| v.0).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn transform_mirror() {
+        let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nvt 0 0\nvn 0 0 1\n\
+                       f 1/1/1 2/1/1 3/1/1 4/1/1\nf 1 2 3\nl 1 2 3\n";
+        let mut data = ObjData::from_str(obj_str).ok().unwrap();
+        data.transform([[-1.,0.,0.,0.],[0.,1.,0.,0.],[0.,0.,1.,0.],[0.,0.,0.,1.]]);
+        assert_eq!((0.,0.,1.),data.normals[0]);
+        assert_eq!((-1.,1.,0.,1.),data.vertices[2]);
+        let face = data.faces.get(0).unwrap();
+        assert_eq!(vec![3,2,1,0],face.positions().map(|v| v.0).collect::<Vec<_>>());
+        assert_eq!(Some(0),face.vertices[0].texcoord.map(|i| i.0));
+        assert_eq!(vec![2,1,0],data.faces.get(1).unwrap().positions().map(|v| v.0).collect::<Vec<_>>());
+        assert_eq!(vec![(0,None),(1,None),(2,None)],data.lines[0]);
+        // Winding is unchanged by a rotation
+        data.y_up_to_z_up();
+        assert_eq!(vec![3,2,1,0],data.faces.get(0).unwrap().positions().map(|v| v.0).collect::<Vec<_>>());
+        assert!(close((0.,-1.,0.),data.normals[0]));
+    }
+
+    #[test]
+    fn recenter() {
+        let mut data = ObjData::from_str("v 1 2 3\nv 3 6 3\nvn 1 0 0\n").ok().unwrap();
+        data.recenter();
+        assert_eq!(vec![(-1.,-2.,0.,1.),(1.,2.,0.,1.)],data.vertices);
+        assert_eq!(vec![(1.,0.,0.)],data.normals);
+
+        // Empty and flat data are unchanged
+        let mut data = ObjData::new();
+        data.scale_to_unit_box();
+        assert!(data.vertices.is_empty());
+        let mut data = ObjData::from_str("v 1 1 1\n").ok().unwrap();
+        data.scale_to_unit_box();
+        assert_eq!(vec![(0.,0.,0.,1.)],data.vertices);
+    }
+}
@@ -0,0 +1,299 @@
+// Positions are converted to f64, which they already are with the `f64` feature.
+#![cfg_attr(feature = "f64", allow(clippy::unnecessary_cast))]
+
+use obj::{ObjData,Element};
+use face::{Face,Faces};
+
+// Tolerance on twice the area of a corner, relative to the size of the polygon.
+const EPSILON : f64 = 1e-12;
+
+fn cross(a : [f64;3], b : [f64;3]) -> [f64;3] {
+    [a[1]*b[2]-a[2]*b[1],a[2]*b[0]-a[0]*b[2],a[0]*b[1]-a[1]*b[0]]
+}
+
+fn dot(a : [f64;3], b : [f64;3]) -> f64 {
+    a[0]*b[0]+a[1]*b[1]+a[2]*b[2]
+}
+
+// Twice the signed area of the triangle `(a,b,c)`, positive if it is counter-clockwise.
+fn area(a : [f64;2], b : [f64;2], c : [f64;2]) -> f64 {
+    (b[0]-a[0])*(c[1]-a[1]) - (b[1]-a[1])*(c[0]-a[0])
+}
+
+// Whether `p` is inside the counter-clockwise triangle `(a,b,c)` or on its boundary.
+fn in_triangle(p : [f64;2], a : [f64;2], b : [f64;2], c : [f64;2], epsilon : f64) -> bool {
+    area(a,b,p) >= -epsilon && area(b,c,p) >= -epsilon && area(c,a,p) >= -epsilon
+}
+
+// Sum of the angles by which the edges of a polygon turn, 2π times its winding number.
+fn turning_angle(points : &[[f64;2]]) -> f64 {
+    let n = points.len();
+    (0..n).map(|i| {
+        let (a,b,c) = (points[i],points[(i+1) % n],points[(i+2) % n]);
+        let (u,v) = ([b[0]-a[0],b[1]-a[1]],[c[0]-b[0],c[1]-b[1]]);
+        (u[0]*v[1] - u[1]*v[0]).atan2(u[0]*v[0] + u[1]*v[1])
+    }).sum()
+}
+
+// Normal of Newell of a polygon, whose length is twice its area when it is planar.
+pub fn newell(points : &[[f64;3]]) -> [f64;3] {
+    let mut normal = [0.;3];
+    for (i,&p) in points.iter().enumerate() {
+        let q = points[(i+1) % points.len()];
+        normal[0] += (p[1]-q[1]) * (p[2]+q[2]);
+        normal[1] += (p[2]-q[2]) * (p[0]+q[0]);
+        normal[2] += (p[0]-q[0]) * (p[1]+q[1]);
+    }
+    normal
+}
+
+// Points of a polygon projected on the plane given by its normal of Newell, so that
+// non-planar polygons are projected on the plane closest to them and turn counter-clockwise.
+// `None` if the polygon has no area.
+fn project(points : &[[f64;3]]) -> Option<Vec<[f64;2]>> {
+    let normal = newell(points);
+    if dot(normal,normal) == 0. {
+        return None;
+    }
+    // Any vector not colinear with the normal gives the first axis of the plane
+    let other = if normal[0].abs() <= normal[1].abs() && normal[0].abs() <= normal[2].abs() {
+        [1.,0.,0.]
+    } else if normal[1].abs() <= normal[2].abs() {
+        [0.,1.,0.]
+    } else {
+        [0.,0.,1.]
+    };
+    let u = cross(other,normal);
+    let v = cross(normal,u);
+    Some(points.iter().map(|&p| [dot(p,u),dot(p,v)]).collect())
+}
+
+// Triangulate the polygon `points` by ear clipping, adding the triangles to `triangles`.
+fn clip_ears(points : &[[f64;2]], epsilon : f64, triangles : &mut Vec<[usize;3]>) {
+    let mut remaining : Vec<usize> = (0..points.len()).collect();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |k : usize| (remaining[(k+n-1) % n],remaining[k],remaining[(k+1) % n]);
+        let ear = (0..n).find(|&k| {
+            let (a,b,c) = corner(k);
+            area(points[a],points[b],points[c]) > epsilon && remaining.iter()
+                .filter(|&&p| p != a && p != b && p != c && points[p] != points[a]
+                        && points[p] != points[b] && points[p] != points[c])
+                .all(|&p| !in_triangle(points[p],points[a],points[b],points[c],epsilon))
+        });
+        // A self-intersecting polygon may have no ear, its most convex corner is clipped instead
+        let k = ear.unwrap_or_else(|| (0..n).max_by(|&i,&j| {
+            let ((a,b,c),(d,e,f)) = (corner(i),corner(j));
+            area(points[a],points[b],points[c]).total_cmp(&area(points[d],points[e],points[f]))
+        }).unwrap());
+        let (a,b,c) = corner(k);
+        triangles.push([a,b,c]);
+        remaining.remove(k);
+    }
+    triangles.push([remaining[0],remaining[1],remaining[2]]);
+}
+
+impl ObjData {
+    /// Triangles covering `face`, given by the indices of their vertices in the face.
+    ///
+    /// Convex faces are split into a fan around their first vertex, other ones by ear clipping
+    /// after being projected on the plane closest to their vertices. The triangles turn
+    /// in the same direction as the face. Faces of less than 3 vertices have no triangles.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// // Concave quad, its vertex 3 is inside the triangle (0,1,2)
+    /// let data = ObjData::from_str("v 0 0 0\nv 4 0 0\nv 0 4 0\nv 1 1 0\nf 1 2 3 4\n").ok().unwrap();
+    /// let face = data.faces.get(0).unwrap();
+    /// assert_eq!(vec![[3,0,1],[1,2,3]],data.triangulate_face(face));
+    ///
+    /// let data = ObjData::from_str("v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\nf 1 2 3 4\n").ok().unwrap();
+    /// let face = data.faces.get(0).unwrap();
+    /// assert_eq!(vec![[0,1,2],[0,2,3]],data.triangulate_face(face));
+    /// ```
+    pub fn triangulate_face(&self, face : Face) -> Vec<[usize;3]> {
+        let mut triangles = Vec::new();
+        self.triangulate_face_into(face,&mut triangles);
+        triangles
+    }
+
+    fn triangulate_face_into(&self, face : Face, triangles : &mut Vec<[usize;3]>) {
+        let n = face.len();
+        if n == 3 {
+            triangles.push([0,1,2]);
+            return;
+        }
+        if n < 3 {
+            return;
+        }
+        let points : Vec<[f64;3]> = self.face_positions(face)
+            .map(|&(x,y,z,_)| [x as f64,y as f64,z as f64]).collect();
+        let fan = |triangles : &mut Vec<[usize;3]>| triangles.extend((1..n-1).map(|i| [0,i,i+1]));
+        let points = match project(&points) {
+            Some(points) => points,
+            None => return fan(triangles),
+        };
+        let size = points.iter().fold(0f64,|size,p| size.max(p[0].abs()).max(p[1].abs()));
+        let epsilon = EPSILON * size * size;
+        // All corners turn left, and only once around like in a star polygon
+        let convex = (0..n).all(|i| area(points[i],points[(i+1) % n],points[(i+2) % n]) >= -epsilon)
+            && turning_angle(&points) < 3. * std::f64::consts::PI;
+        if convex {
+            fan(triangles);
+        } else {
+            clip_ears(&points,epsilon,triangles);
+        }
+    }
+
+    /// Replace every face by triangles given by `triangulate_face`.
+    ///
+    /// Objects, groups, materials and smoothing groups of the faces are updated to
+    /// the triangles, which replace each face in order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use lwobj::ObjData;
+    ///
+    /// let obj_str = "v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\no quad\nf 1 2 3 4\no triangle\nf 1 2 3\n";
+    /// let mut data = ObjData::from_str(obj_str).ok().unwrap();
+    /// data.triangulate();
+    /// assert_eq!(3,data.faces.len());
+    /// assert_eq!(vec![0,1],data.objects[0].primitives);
+    /// assert_eq!(vec![2],data.objects[1].primitives);
+    /// ```
+    pub fn triangulate(&mut self) {
+        let mut faces = Faces::new();
+        // Triangles replacing each face are `first[i]..first[i+1]`
+        let mut first = Vec::with_capacity(self.faces.len()+1);
+        let mut triangles = Vec::new();
+        for face in &self.faces {
+            first.push(faces.len());
+            triangles.clear();
+            self.triangulate_face_into(face,&mut triangles);
+            for triangle in &triangles {
+                faces.push(triangle.iter().map(|&k| face.vertices[k]));
+            }
+        }
+        first.push(faces.len());
+        self.faces = faces;
+
+        let remap = |faces : &[usize]| -> Vec<usize> {
+            faces.iter().filter(|&&i| i+1 < first.len()).flat_map(|&i| first[i]..first[i+1]).collect()
+        };
+        for object in &mut self.objects {
+            object.primitives = remap(&object.primitives);
+            object.elements = object.elements.iter().flat_map(|&element| match element {
+                Element::Face(i) if i+1 < first.len() => (first[i]..first[i+1]).map(Element::Face).collect(),
+                Element::Face(_) => Vec::new(),
+                element => vec![element],
+            }).collect();
+        }
+        for group in &mut self.groups {
+            group.indexes = remap(&group.indexes);
+        }
+        let repeat = |i : usize| first[i+1] - first[i];
+        self.face_materials = self.face_materials.iter().enumerate().take(first.len()-1)
+            .flat_map(|(i,&m)| (0..repeat(i)).map(move |_| m)).collect();
+        self.smoothing_groups = self.smoothing_groups.iter().enumerate().take(first.len()-1)
+            .flat_map(|(i,&s)| (0..repeat(i)).map(move |_| s)).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use obj::*;
+    use face::FaceVertex;
+    use triangulate::*;
+
+    // Check that the triangles of `face` in the plane z = 0 have the same area as the face
+    // and turn like it.
+    fn check_area(data : &ObjData, face : usize, triangles : &[[usize;3]]) {
+        let face = data.faces.get(face).unwrap();
+        let points : Vec<[f64;2]> = data.face_positions(face).map(|&(x,y,_,_)| [x as f64,y as f64]).collect();
+        let expected : f64 = (1..points.len()-1).map(|i| area(points[0],points[i],points[i+1])).sum();
+        let mut total = 0.;
+        for t in triangles {
+            let a = area(points[t[0]],points[t[1]],points[t[2]]);
+            assert!(a * expected > 0.);
+            total += a;
+        }
+        assert_eq!(expected,total);
+    }
+
+    #[test]
+    fn triangulate_convex() {
+        let data = ObjData::from_str("v 0 0 0\nv 2 0 0\nv 3 1 0\nv 2 2 0\nv 0 2 0\nf 1 2 3 4 5\nf 1 2 3\n").ok().unwrap();
+        let triangles = data.triangulate_face(data.faces.get(0).unwrap());
+        assert_eq!(vec![[0,1,2],[0,2,3],[0,3,4]],triangles);
+        assert_eq!(vec![[0,1,2]],data.triangulate_face(data.faces.get(1).unwrap()));
+    }
+
+    #[test]
+    fn triangulate_concave() {
+        // L shape, and a star with 5 branches
+        let obj_str = "v 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 3 0\nv 0 3 0\nf 1 2 3 4 5 6\n\
+                       v 0 10 0\nv 2 13 0\nv 5 13 0\nv 3 15 0\nv 4 18 0\nv 0 16 0\nv -4 18 0\nv -3 15 0\nv -5 13 0\nv -2 13 0\n\
+                       f 7 8 9 10 11 12 13 14 15 16\nf 16 15 14 13 12 11 10 9 8 7\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let triangles = data.triangulate_face(data.faces.get(0).unwrap());
+        assert_eq!(4,triangles.len());
+        check_area(&data,0,&triangles);
+        for face in 1..3 {
+            let triangles = data.triangulate_face(data.faces.get(face).unwrap());
+            assert_eq!(8,triangles.len());
+            check_area(&data,face,&triangles);
+        }
+    }
+
+    #[test]
+    fn triangulate_star_polygon() {
+        // Pentagram, whose corners all turn left but which turns twice around its center
+        let obj_str = "v 0 10 0\nv 5.88 -8.09 0\nv -9.51 3.09 0\nv 9.51 3.09 0\nv -5.88 -8.09 0\nf 1 5 4 3 2\n";
+        let data = ObjData::from_str(obj_str).ok().unwrap();
+        let face = data.faces.get(0).unwrap();
+        let points : Vec<[f64;2]> = data.face_positions(face).map(|&(x,y,_,_)| [x as f64,y as f64]).collect();
+        assert!((turning_angle(&points) - 4. * std::f64::consts::PI).abs() < 1e-9);
+        let triangles = data.triangulate_face(face);
+        assert_eq!(3,triangles.len());
+        // Ear clipping is used instead of a fan, uses every corner and covers the same signed area
+        assert!((0..5).all(|i| triangles.iter().any(|t| t.contains(&i))));
+        assert_ne!(vec![[0,1,2],[0,2,3],[0,3,4]],triangles);
+        let expected : f64 = (1..4).map(|i| area(points[0],points[i],points[i+1])).sum();
+        let total : f64 = triangles.iter().map(|t| area(points[t[0]],points[t[1]],points[t[2]])).sum();
+        assert!((expected-total).abs() < 1e-9);
+    }
+
+    #[test]
+    fn triangulate_non_planar() {
+        // Concave quad bent along its diagonal, and a face with repeated positions
+        let data = ObjData::from_str("v 0 0 0\nv 4 0 1\nv 0 4 1\nv 1 1 0\nf 1 2 3 4\nf 1 2 2 3\nf 1 1 1\n").ok().unwrap();
+        assert_eq!(vec![[3,0,1],[1,2,3]],data.triangulate_face(data.faces.get(0).unwrap()));
+        assert_eq!(2,data.triangulate_face(data.faces.get(1).unwrap()).len());
+        assert_eq!(vec![[0,1,2]],data.triangulate_face(data.faces.get(2).unwrap()));
+    }
+
+    #[test]
+    fn triangulate_data() {
+        let obj_str = "mtllib cube.mtl\nv 0 0 0\nv 2 0 0\nv 2 1 0\nv 1 1 0\nv 1 3 0\nv 0 3 0\nvt 0 0\nvn 0 0 1\n\
+                       o A\ng gr1\nusemtl Red\ns 1\nf 1/1/1 2/1/1 3/1/1 4/1/1\ng gr1 gr2\nf 1 2 3\n\
+                       o B\ng gr2\nusemtl Blue\ns off\nf 1 2 3 4 5 6\n";
+        let mut data = ObjData::from_str(obj_str).ok().unwrap();
+        data.triangulate();
+        assert_eq!(7,data.faces.len());
+        assert_eq!(data.faces.get(1).unwrap().vertices,&[FaceVertex::new(0,Some(0),Some(0)),
+                   FaceVertex::new(2,Some(0),Some(0)),FaceVertex::new(3,Some(0),Some(0))]);
+        assert_eq!(vec![0,1,2],data.objects[0].primitives);
+        assert_eq!(vec![3,4,5,6],data.objects[1].primitives);
+        assert_eq!(vec![0,1,2],data.groups[0].indexes);
+        assert_eq!(vec![2,3,4,5,6],data.groups[1].indexes);
+        assert_eq!(vec![Some(0),Some(0),Some(0),Some(1),Some(1),Some(1),Some(1)],data.face_materials);
+        assert_eq!(vec![1,1,1,0,0,0,0],data.smoothing_groups);
+        for i in 0..data.faces.len() {
+            assert_eq!(3,data.faces.get(i).unwrap().len());
+        }
+    }
+}
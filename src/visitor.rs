@@ -0,0 +1,279 @@
+use std::io;
+use obj::{Float,LoadingError,LoadOptions,MaxIndexes,SourceLine};
+use freeform::{Curve,Curve2,Surface,FreeFormParser};
+use face::FaceVertex;
+use reader::{ObjReader,Statement};
+
+/// Callbacks receiving the statements of a file parsed by `visit`.
+///
+/// Indices given to the callbacks start at 0, relative indices being already resolved.
+/// Every callback does nothing by default.
+pub trait ObjVisitor {
+    /// Vertex `(x,y,z,w)` given by `v`.
+    fn vertex(&mut self, _v : (Float,Float,Float,Float)) {}
+    /// Normal given by `vn`.
+    fn normal(&mut self, _vn : (Float,Float,Float)) {}
+    /// Texture coordinates `(u,v,w)` given by `vt`.
+    fn texcoord(&mut self, _vt : (Float,Float,Float)) {}
+    /// Parameter vertex `(u,v,w)` given by `vp`.
+    fn parameter_vertex(&mut self, _vp : (Float,Float,Float)) {}
+    /// Face given by `f`, as the indices of the position, texture coordinates and normal of each corner.
+    fn face(&mut self, _vertices : &[FaceVertex]) {}
+    /// Polyline given by `l`, as `(vertex,texcoord)` indices.
+    fn line(&mut self, _vertices : &[(usize,Option<usize>)]) {}
+    /// Points given by `p`.
+    fn point(&mut self, _vertices : &[usize]) {}
+    /// Start of an object given by `o`.
+    fn object(&mut self, _name : &str) {}
+    /// Groups given by `g`, applying to the following elements.
+    fn group(&mut self, _names : &[&str]) {}
+    /// Smoothing group given by `s`, 0 meaning that smoothing is off.
+    fn smoothing_group(&mut self, _group : u32) {}
+    /// Material library files given by `mtllib`.
+    fn material_library(&mut self, _files : &[&str]) {}
+    /// Material given by `usemtl`, `name` being empty for `usemtl` alone which selects no material.
+    fn material(&mut self, _name : &str) {}
+    /// Curve given by `curv` once closed by `end`.
+    fn curve(&mut self, _curve : Curve) {}
+    /// 2D curve given by `curv2` once closed by `end`.
+    fn curve2(&mut self, _curve : Curve2) {}
+    /// Surface given by `surf` once closed by `end`.
+    fn surface(&mut self, _surface : Surface) {}
+    /// Unsupported statement found by a lenient parsing, `warning` locating it.
+    fn unknown(&mut self, _text : &str, _warning : LoadingError) {}
+}
+
+/// Parse a file with `options`, giving each statement to `visitor` without storing elements.
+///
+/// Indices are checked once the whole file is parsed, so `visitor` may already have
+/// received some statements when an error is returned.
+///
+/// # Examples
+///
+/// ```
+/// use std::io::BufReader;
+/// use lwobj::{ObjVisitor,FaceVertex,LoadOptions,visit};
+///
+/// struct FaceCounter {
+///     faces : usize,
+/// }
+///
+/// impl ObjVisitor for FaceCounter {
+///     fn face(&mut self, _vertices : &[FaceVertex]) {
+///         self.faces += 1;
+///     }
+/// }
+///
+/// let mut input = BufReader::new("v 0 0 0\nv 1 0 0\nv 0 1 0\nf 1 2 3\nf 3 2 1\n".as_bytes());
+/// let mut counter = FaceCounter { faces : 0 };
+/// assert!(visit(&mut input,&LoadOptions::default(),&mut counter).is_ok());
+/// assert_eq!(2,counter.faces);
+/// ```
+pub fn visit<R : io::BufRead, V : ObjVisitor>(input : &mut R, options : &LoadOptions, visitor : &mut V)
+    -> Result<(),LoadingError> {
+    let mut reader = ObjReader::with_options(input,options);
+    let mut state = VisitState::new();
+    while let Some(statement) = reader.next() {
+        let statement = statement?;
+        state.apply(visitor,statement,&reader.source_line())?;
+    }
+    state.finish()
+}
+
+// Indices and unclosed free-form element tracked while giving statements to a visitor,
+// with buffers reused by every element.
+pub struct VisitState {
+    max : MaxIndexes,
+    freeform : FreeFormParser,
+    face : Vec<FaceVertex>,
+    polyline : Vec<(usize,Option<usize>)>,
+    points : Vec<usize>,
+}
+
+impl VisitState {
+    pub fn new() -> VisitState {
+        VisitState {
+            max : MaxIndexes::new(),
+            freeform : FreeFormParser::new(),
+            face : Vec::new(),
+            polyline : Vec::new(),
+            points : Vec::new(),
+        }
+    }
+
+    // Give `statement`, read from `line`, to `visitor`.
+    pub fn apply<V : ObjVisitor>(&mut self, visitor : &mut V, statement : Statement, line : &SourceLine) -> Result<(),LoadingError> {
+        let max = &mut self.max;
+        match statement {
+            Statement::Vertex(v) => self.vertex(visitor,v),
+            Statement::Normal(vn) => self.normal(visitor,vn),
+            Statement::TexCoord(vt) => self.texcoord(visitor,vt),
+            Statement::ParameterVertex(vp) => {
+                visitor.parameter_vertex(vp);
+                max.vp.len += 1;
+            },
+            Statement::SmoothingGroup(group) => visitor.smoothing_group(group),
+            Statement::Face(vertices) => self.face(visitor,&vertices,line,|k| line.argument(k))?,
+            Statement::Line(vertices) => {
+                self.polyline.clear();
+                for (k,(v,vt)) in vertices.into_iter().enumerate() {
+                    let v = max.v.resolve(v,line,k,0)?;
+                    let vt = match vt {
+                        Some(i) => Some(max.vt.resolve(i,line,k,1)?),
+                        None => None,
+                    };
+                    self.polyline.push((v,vt));
+                }
+                visitor.line(&self.polyline);
+            },
+            Statement::Point(vertices) => {
+                self.points.clear();
+                for (k,v) in vertices.into_iter().enumerate() {
+                    self.points.push(max.v.resolve(v,line,k,0)?);
+                }
+                visitor.point(&self.points);
+            },
+            Statement::Object(name) => visitor.object(&name),
+            Statement::Group(names) => {
+                let names : Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+                visitor.group(&names);
+            },
+            Statement::MaterialLibrary(files) => {
+                let files : Vec<&str> = files.iter().map(|file| file.as_str()).collect();
+                visitor.material_library(&files);
+            },
+            Statement::Material(name) => visitor.material(&name),
+            Statement::Unknown(text) => visitor.unknown(&text,line.invalid(line.keyword(),"a supported statement")),
+            statement => self.freeform.parse(visitor,max,statement,line)?,
+        }
+        Ok(())
+    }
+
+    pub fn vertex<V : ObjVisitor>(&mut self, visitor : &mut V, v : (Float,Float,Float,Float)) {
+        visitor.vertex(v);
+        self.max.v.len += 1;
+    }
+
+    pub fn normal<V : ObjVisitor>(&mut self, visitor : &mut V, vn : (Float,Float,Float)) {
+        visitor.normal(vn);
+        self.max.vn.len += 1;
+    }
+
+    pub fn texcoord<V : ObjVisitor>(&mut self, visitor : &mut V, vt : (Float,Float,Float)) {
+        visitor.texcoord(vt);
+        self.max.vt.len += 1;
+    }
+
+    // Give the face made of the `v/vt/vn` references `vertices`, read from `line`, to `visitor`.
+    // `args` gives the argument of each reference, only called to locate it.
+    #[allow(clippy::type_complexity)]
+    pub fn face<'t, V : ObjVisitor, F : Fn(usize) -> &'t str>(&mut self, visitor : &mut V, vertices : &[(isize,Option<isize>,Option<isize>)],
+                                                           line : &SourceLine, args : F) -> Result<(),LoadingError> {
+        self.face.clear();
+        for (k,&vertex) in vertices.iter().enumerate() {
+            self.face.push(FaceVertex::from(self.max.resolve_vertex_token(vertex,line,|| args(k))?));
+        }
+        visitor.face(&self.face);
+        Ok(())
+    }
+
+    // Check what can only be checked once every statement is given.
+    pub fn finish(&self) -> Result<(),LoadingError> {
+        self.freeform.finish()?;
+        self.max.check()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufReader;
+    use obj::*;
+    use visitor::*;
+
+    // Record the callbacks as strings.
+    struct Recorder {
+        calls : Vec<String>,
+    }
+
+    impl ObjVisitor for Recorder {
+        fn vertex(&mut self, v : (Float,Float,Float,Float)) {
+            self.calls.push(format!("v {:?}",v));
+        }
+
+        fn face(&mut self, vertices : &[FaceVertex]) {
+            let positions : Vec<usize> = vertices.iter().map(|vertex| vertex.position.0).collect();
+            self.calls.push(format!("f {:?}",positions));
+        }
+
+        fn object(&mut self, name : &str) {
+            self.calls.push(format!("o {}",name));
+        }
+
+        fn group(&mut self, names : &[&str]) {
+            self.calls.push(format!("g {:?}",names));
+        }
+
+        fn material(&mut self, name : &str) {
+            self.calls.push(format!("usemtl {}",name));
+        }
+
+        fn curve(&mut self, curve : Curve) {
+            self.calls.push(format!("curv {:?}",curve.control_points));
+        }
+
+        fn unknown(&mut self, text : &str, warning : LoadingError) {
+            self.calls.push(format!("unknown {} {}",text,warning.context().unwrap().line));
+        }
+    }
+
+    #[test]
+    fn visit_statements() {
+        let obj_str =
+        r#"o Test
+        v 0 0 0
+        v 1 0 0
+        v 0 1 0
+        g gr1 gr2
+        usemtl Red
+        f 1 -2 -1
+        vn 0 0 1
+        cstype bezier
+        deg 1
+        curv 0 1 1 2
+        end
+        lod 3"#;
+
+        let expected = vec!["o Test",
+        "v (0.0, 0.0, 0.0, 1.0)",
+        "v (1.0, 0.0, 0.0, 1.0)",
+        "v (0.0, 1.0, 0.0, 1.0)",
+        "g [\"gr1\", \"gr2\"]",
+        "usemtl Red",
+        "f [0, 1, 2]",
+        "curv [0, 1]",
+        "unknown lod 3 13"];
+        let mut recorder = Recorder { calls : Vec::new() };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        let options = LoadOptions { lenient : true };
+        assert!(visit(&mut input,&options,&mut recorder).is_ok());
+        assert_eq!(expected,recorder.calls);
+    }
+
+    #[test]
+    fn visit_index_out_of_range() {
+        let obj_str =
+        r#"v 0 0 0
+        f 1 2 3"#;
+
+        let mut recorder = Recorder { calls : Vec::new() };
+        let mut input = BufReader::new(obj_str.as_bytes());
+        match visit(&mut input,&LoadOptions::default(),&mut recorder).err().unwrap() {
+            LoadingError::IndexOutOfRange { context, kind, index } => {
+                assert_eq!((2,IndexKind::Vertex,3),(context.line,kind,index));
+            },
+            _ => panic!(),
+        };
+        // Forward references are only checked at the end
+        assert_eq!(2,recorder.calls.len());
+    }
+}